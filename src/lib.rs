@@ -0,0 +1,2077 @@
+//! Keybind loading and searching for nebula-keybind-menu. The binary is a
+//! TUI on top of this; other programs can use it to read the same configs
+//! and filter keybinds the same way.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier},
+    text::Span,
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct Keybind {
+    // Only `name` is needed for an entry that just hides a system keybind.
+    #[serde(default, deserialize_with = "deserialize_keys")]
+    pub keys: String,
+    pub name: String,
+    #[serde(default)]
+    pub desc: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    // Other words the entry is searched by, such as "firefox" for a browser;
+    // never drawn
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    // Shell command run by Enter in --launcher mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    // `false` keeps the entry out of the menu unless --show-disabled is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    // Set in the user config to drop a system keybind with the same name
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub hidden: bool,
+    // Tints the name, e.g. "red" or "#ff8800"; unset uses the theme's color
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    // The file the entry was loaded from, when the config spans several
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+}
+
+impl Keybind {
+    // The parsed `color`, or None when it's unset or not a color
+    pub fn color(&self) -> Option<Color> {
+        self.color.as_deref()?.parse().ok()
+    }
+}
+
+// One message per keybind whose `color` can't be parsed. These are only
+// warnings: such entries just keep the theme's color.
+pub fn color_warnings(items: &[Keybind]) -> Vec<String> {
+    items
+        .iter()
+        .filter_map(|item| {
+            let color = item.color.as_deref()?;
+            match color.parse::<Color>() {
+                Ok(_) => None,
+                Err(_) => Some(format!("{}: unknown color \"{}\"", item.name, color)),
+            }
+        })
+        .collect()
+}
+
+// Joins the steps of a key sequence such as "SUPER + W then H"
+pub const SEQUENCE_SEPARATOR: &str = " then ";
+
+// `keys` is a combo, or a sequence as one string or an array of steps
+fn deserialize_keys<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Keys {
+        Combo(String),
+        Steps(Vec<String>),
+    }
+    Ok(match Keys::deserialize(deserializer)? {
+        Keys::Combo(keys) => keys,
+        Keys::Steps(steps) => steps.join(SEQUENCE_SEPARATOR),
+    })
+}
+
+#[derive(Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    #[default]
+    Fuzzy,
+    Substring,
+    Regex,
+}
+
+// Order of the result list
+#[derive(Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortMode {
+    // Best match first while searching, config order otherwise
+    #[default]
+    Relevance,
+    Config,
+    Name,
+    Keys,
+    // Most used first, from the usage counts in `SearchOptions`
+    Frequent,
+}
+
+impl SortMode {
+    pub fn next(self) -> Self {
+        match self {
+            SortMode::Relevance => SortMode::Config,
+            SortMode::Config => SortMode::Name,
+            SortMode::Name => SortMode::Keys,
+            SortMode::Keys => SortMode::Frequent,
+            SortMode::Frequent => SortMode::Relevance,
+        }
+    }
+}
+
+// Where an entry's name goes on its line
+#[derive(Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NameAlign {
+    // Against the right edge
+    #[default]
+    Right,
+    // After a key column of fixed width, so names line up
+    Left,
+}
+
+// How the search input is drawn
+#[derive(Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchStyle {
+    // A bordered box below a blank row, four rows in all
+    #[default]
+    Bordered,
+    // A single row with a prompt and no border
+    Compact,
+}
+
+// The optional `[layout]` table
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct LayoutSettings {
+    pub search_style: SearchStyle,
+}
+
+// Colors for each UI element, parsed from names ("green") or hex ("#00ff00")
+#[derive(Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub title: Color,
+    pub border: Color,
+    pub description: Color,
+    // Secondary text: hints, placeholder, labels and description dashes
+    pub dim: Color,
+    pub key: Color,
+    // Modifier keys such as SUPER or CTRL within a key combo
+    pub modifier: Color,
+    pub name: Color,
+    pub selected: Color,
+    pub highlight: Color,
+    pub text: Color,
+    // `!!text!!` in a description
+    pub warning: Color,
+    // How key combos and names stand out from the rest of the text
+    pub key_style: TextStyle,
+    pub name_style: TextStyle,
+}
+
+// A text attribute for the theme's emphasized elements
+#[derive(Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TextStyle {
+    #[default]
+    Bold,
+    Italic,
+    Underline,
+    None,
+}
+
+impl TextStyle {
+    pub fn modifier(self) -> Modifier {
+        match self {
+            TextStyle::Bold => Modifier::BOLD,
+            TextStyle::Italic => Modifier::ITALIC,
+            TextStyle::Underline => Modifier::UNDERLINED,
+            TextStyle::None => Modifier::empty(),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            title: Color::Green,
+            border: Color::DarkGray,
+            description: Color::Gray,
+            dim: Color::DarkGray,
+            key: Color::White,
+            modifier: Color::Cyan,
+            name: Color::White,
+            selected: Color::DarkGray,
+            highlight: Color::Yellow,
+            text: Color::White,
+            warning: Color::LightRed,
+            key_style: TextStyle::Bold,
+            name_style: TextStyle::Bold,
+        }
+    }
+}
+
+// Names accepted by --theme and `theme = "..."`
+const THEME_NAMES: [&str; 4] = ["dark", "light", "solarized", "nord"];
+
+impl Theme {
+    // A built-in theme by name; "dark" is the default
+    fn named(name: &str) -> Option<Self> {
+        let theme = match name {
+            "dark" => Self::default(),
+            "light" => Self {
+                title: Color::Blue,
+                border: Color::Gray,
+                description: Color::DarkGray,
+                dim: Color::DarkGray,
+                key: Color::Black,
+                modifier: Color::Magenta,
+                name: Color::Black,
+                selected: Color::Gray,
+                highlight: Color::Red,
+                text: Color::Black,
+                warning: Color::Red,
+                ..Self::default()
+            },
+            "solarized" => Self {
+                title: Color::Rgb(0x85, 0x99, 0x00),
+                border: Color::Rgb(0x58, 0x6e, 0x75),
+                description: Color::Rgb(0x93, 0xa1, 0xa1),
+                dim: Color::Rgb(0x58, 0x6e, 0x75),
+                key: Color::Rgb(0xee, 0xe8, 0xd5),
+                modifier: Color::Rgb(0x26, 0x8b, 0xd2),
+                name: Color::Rgb(0xee, 0xe8, 0xd5),
+                selected: Color::Rgb(0x07, 0x36, 0x42),
+                highlight: Color::Rgb(0xb5, 0x89, 0x00),
+                text: Color::Rgb(0x83, 0x94, 0x96),
+                warning: Color::Rgb(0xdc, 0x32, 0x2f),
+                ..Self::default()
+            },
+            "nord" => Self {
+                title: Color::Rgb(0x88, 0xc0, 0xd0),
+                border: Color::Rgb(0x4c, 0x56, 0x6a),
+                description: Color::Rgb(0xd8, 0xde, 0xe9),
+                dim: Color::Rgb(0x61, 0x6e, 0x88),
+                key: Color::Rgb(0xec, 0xef, 0xf4),
+                modifier: Color::Rgb(0x81, 0xa1, 0xc1),
+                name: Color::Rgb(0xec, 0xef, 0xf4),
+                selected: Color::Rgb(0x3b, 0x42, 0x52),
+                highlight: Color::Rgb(0xeb, 0xcb, 0x8b),
+                text: Color::Rgb(0xe5, 0xe9, 0xf0),
+                warning: Color::Rgb(0xbf, 0x61, 0x6a),
+                ..Self::default()
+            },
+            _ => return None,
+        };
+        Some(theme)
+    }
+
+    // Like `named`, with an error that lists the built-in themes
+    pub fn by_name(name: &str) -> Result<Self, String> {
+        Self::named(name).ok_or_else(|| {
+            format!(
+                "unknown theme: {} (available: {})",
+                name,
+                THEME_NAMES.join(", ")
+            )
+        })
+    }
+
+    // The theme with every color reduced to what the terminal can show
+    pub fn with_depth(self, depth: ColorDepth) -> Self {
+        let fit = |color| depth.fit(color);
+        Self {
+            title: fit(self.title),
+            border: fit(self.border),
+            description: fit(self.description),
+            dim: fit(self.dim),
+            key: fit(self.key),
+            modifier: fit(self.modifier),
+            name: fit(self.name),
+            selected: fit(self.selected),
+            highlight: fit(self.highlight),
+            text: fit(self.text),
+            warning: fit(self.warning),
+            ..self
+        }
+    }
+
+    // Leaves everything in the terminal's default colors
+    pub fn monochrome() -> Self {
+        Self {
+            title: Color::Reset,
+            border: Color::Reset,
+            description: Color::Reset,
+            dim: Color::Reset,
+            key: Color::Reset,
+            modifier: Color::Reset,
+            name: Color::Reset,
+            selected: Color::Reset,
+            highlight: Color::Reset,
+            text: Color::Reset,
+            warning: Color::Reset,
+            ..Self::default()
+        }
+    }
+}
+
+// How many colors the terminal can show
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColorDepth {
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+// The 16 ANSI colors with xterm's default values, in palette order
+const ANSI_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+// Channel values of the 6x6x6 cube in the 256-color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorDepth {
+    // Guesses from COLORTERM and TERM, assuming 16 colors when neither says more
+    pub fn detect() -> Self {
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+        let term = std::env::var("TERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+
+    // The nearest color this depth can show; named colors always fit
+    pub fn fit(self, color: Color) -> Color {
+        match (self, color) {
+            (ColorDepth::TrueColor, _) => color,
+            (ColorDepth::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(nearest_indexed(r, g, b)),
+            (ColorDepth::Ansi16, Color::Rgb(r, g, b)) => nearest_ansi(r, g, b),
+            (ColorDepth::Ansi16, Color::Indexed(index)) => match ANSI_COLORS.get(index as usize) {
+                Some(&(ansi, _)) => ansi,
+                None => {
+                    let (r, g, b) = indexed_rgb(index);
+                    nearest_ansi(r, g, b)
+                }
+            },
+            _ => color,
+        }
+    }
+}
+
+fn color_distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (i32::from(x) - i32::from(y)).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+fn nearest_ansi(r: u8, g: u8, b: u8) -> Color {
+    ANSI_COLORS
+        .iter()
+        .min_by_key(|(_, rgb)| color_distance(*rgb, (r, g, b)))
+        .map_or(Color::Reset, |&(color, _)| color)
+}
+
+// The closest entry in the cube or the gray ramp of the 256-color palette
+fn nearest_indexed(r: u8, g: u8, b: u8) -> u8 {
+    let level = |value: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| (i32::from(CUBE_LEVELS[i]) - i32::from(value)).abs())
+            .unwrap_or(0) as u8
+    };
+    let cube = 16 + 36 * level(r) + 6 * level(g) + level(b);
+    let average = (u32::from(r) + u32::from(g) + u32::from(b)) / 3;
+    let gray = 232 + (average.saturating_sub(3) / 10).min(23) as u8;
+    if color_distance(indexed_rgb(gray), (r, g, b)) < color_distance(indexed_rgb(cube), (r, g, b)) {
+        gray
+    } else {
+        cube
+    }
+}
+
+// The usual RGB value of a 256-color palette entry
+fn indexed_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI_COLORS[index as usize].1,
+        16..=231 => {
+            let i = index - 16;
+            (
+                CUBE_LEVELS[(i / 36) as usize],
+                CUBE_LEVELS[(i / 6 % 6) as usize],
+                CUBE_LEVELS[(i % 6) as usize],
+            )
+        }
+        _ => {
+            let value = 8 + 10 * (index - 232);
+            (value, value, value)
+        }
+    }
+}
+
+// App actions that can be rebound in the `[controls]` table
+#[derive(Clone, Copy, PartialEq)]
+pub enum Action {
+    Quit,
+    NormalMode,
+    ScrollUp,
+    ScrollDown,
+    PageUp,
+    PageDown,
+    Copy,
+    CopyLine,
+    Reload,
+    ToggleRegex,
+    ToggleCase,
+    CycleSort,
+    NextMatch,
+    PreviousMatch,
+    Details,
+    ClearSearch,
+    ToggleCompact,
+    JumpToCategory,
+    ReverseSort,
+    HistoryPrevious,
+    HistoryNext,
+    EditConfig,
+}
+
+// A key plus modifiers, parsed from strings like "ctrl+r", "pagedown" or "q"
+#[derive(Clone, Copy, PartialEq)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    pub const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    fn matches(&self, key: &KeyEvent) -> bool {
+        // Shift is already implied by a character's case, or by BackTab.
+        let modifiers = match key.code {
+            KeyCode::Char(_) | KeyCode::BackTab => key.modifiers - KeyModifiers::SHIFT,
+            _ => key.modifiers,
+        };
+        self.code == key.code && self.modifiers == modifiers
+    }
+
+    // A bare character, which types into the search box in insert mode
+    pub fn is_plain_char(&self) -> bool {
+        matches!(self.code, KeyCode::Char(_)) && self.modifiers.is_empty()
+    }
+}
+
+impl std::str::FromStr for KeyBinding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // A trailing "+" is the plus key itself, as in "+" or "ctrl++".
+        let (mods, key) = match s.strip_suffix("++") {
+            _ if s == "+" => ("", "+"),
+            Some(mods) => (mods, "+"),
+            None => s.rsplit_once('+').unwrap_or(("", s)),
+        };
+        let mut modifiers = KeyModifiers::NONE;
+        for part in mods.split('+').filter(|part| !part.is_empty()) {
+            modifiers |= match part.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier \"{}\" in \"{}\"", part, s)),
+            };
+        }
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(ch), None) => KeyCode::Char(ch),
+            _ => match key.to_lowercase().as_str() {
+                "esc" | "escape" => KeyCode::Esc,
+                "enter" | "return" => KeyCode::Enter,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" | "pgup" => KeyCode::PageUp,
+                "pagedown" | "pgdn" => KeyCode::PageDown,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "space" => KeyCode::Char(' '),
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n @ 1..=24) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key \"{}\"", s)),
+                },
+            },
+        };
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl std::fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            f.write_str("Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            f.write_str("Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            f.write_str("Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(ch) => write!(f, "{}", ch),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            KeyCode::Up => f.write_str("↑"),
+            KeyCode::Down => f.write_str("↓"),
+            KeyCode::Left => f.write_str("←"),
+            KeyCode::Right => f.write_str("→"),
+            KeyCode::PageUp => f.write_str("PgUp"),
+            KeyCode::PageDown => f.write_str("PgDn"),
+            KeyCode::BackTab => f.write_str("Shift+Tab"),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyBinding {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+// One key or a list of keys for an action
+#[derive(Clone)]
+pub struct KeyList(pub Vec<KeyBinding>);
+
+impl<'de> Deserialize<'de> for KeyList {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // A hand-written visitor keeps the key parse error, which an
+        // untagged enum would replace with a generic message.
+        struct KeyListVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for KeyListVisitor {
+            type Value = KeyList;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a key like \"ctrl+r\" or a list of keys")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<KeyList, E> {
+                value
+                    .parse()
+                    .map(|key| KeyList(vec![key]))
+                    .map_err(E::custom)
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> Result<KeyList, A::Error> {
+                let mut keys = Vec::new();
+                while let Some(key) = seq.next_element()? {
+                    keys.push(key);
+                }
+                Ok(KeyList(keys))
+            }
+        }
+
+        deserializer.deserialize_any(KeyListVisitor)
+    }
+}
+
+// `theme` is either a built-in theme's name or a table of colors
+fn deserialize_theme<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Theme, D::Error> {
+    struct ThemeVisitor;
+
+    impl<'de> serde::de::Visitor<'de> for ThemeVisitor {
+        type Value = Theme;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("a theme name like \"nord\" or a table of colors")
+        }
+
+        fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Theme, E> {
+            Theme::by_name(value).map_err(E::custom)
+        }
+
+        fn visit_map<A: serde::de::MapAccess<'de>>(self, map: A) -> Result<Theme, A::Error> {
+            Theme::deserialize(serde::de::value::MapAccessDeserializer::new(map))
+        }
+    }
+
+    deserializer.deserialize_any(ThemeVisitor)
+}
+
+// Keys for the app's own controls. Unset actions keep their defaults.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct Controls {
+    pub quit: KeyList,
+    pub normal_mode: KeyList,
+    pub scroll_up: KeyList,
+    pub scroll_down: KeyList,
+    pub page_up: KeyList,
+    pub page_down: KeyList,
+    pub copy: KeyList,
+    pub copy_line: KeyList,
+    pub reload: KeyList,
+    pub toggle_regex: KeyList,
+    pub toggle_case: KeyList,
+    pub cycle_sort: KeyList,
+    pub next_match: KeyList,
+    pub previous_match: KeyList,
+    pub details: KeyList,
+    pub clear_search: KeyList,
+    pub toggle_compact: KeyList,
+    pub jump_to_category: KeyList,
+    pub reverse_sort: KeyList,
+    pub history_previous: KeyList,
+    pub history_next: KeyList,
+    pub edit_config: KeyList,
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        let key = |code| KeyBinding::new(code, KeyModifiers::NONE);
+        let ctrl = |ch| KeyBinding::new(KeyCode::Char(ch), KeyModifiers::CONTROL);
+        let alt = |ch| KeyBinding::new(KeyCode::Char(ch), KeyModifiers::ALT);
+        Self {
+            quit: KeyList(vec![ctrl('c')]),
+            normal_mode: KeyList(vec![key(KeyCode::Esc)]),
+            scroll_up: KeyList(vec![key(KeyCode::Up)]),
+            scroll_down: KeyList(vec![key(KeyCode::Down)]),
+            page_up: KeyList(vec![key(KeyCode::PageUp)]),
+            page_down: KeyList(vec![key(KeyCode::PageDown)]),
+            copy: KeyList(vec![key(KeyCode::Enter)]),
+            copy_line: KeyList(vec![ctrl('y')]),
+            reload: KeyList(vec![key(KeyCode::F(5)), ctrl('r')]),
+            toggle_regex: KeyList(vec![alt('r')]),
+            toggle_case: KeyList(vec![alt('c')]),
+            cycle_sort: KeyList(vec![ctrl('s')]),
+            next_match: KeyList(vec![key(KeyCode::Tab)]),
+            previous_match: KeyList(vec![key(KeyCode::BackTab)]),
+            details: KeyList(vec![key(KeyCode::Char('?')), ctrl('o')]),
+            clear_search: KeyList(vec![ctrl('u')]),
+            toggle_compact: KeyList(vec![alt('v')]),
+            jump_to_category: KeyList(vec![key(KeyCode::Char(':')), ctrl('g')]),
+            reverse_sort: KeyList(vec![alt('s')]),
+            history_previous: KeyList(vec![ctrl('p')]),
+            history_next: KeyList(vec![ctrl('n')]),
+            edit_config: KeyList(vec![key(KeyCode::Char('e'))]),
+        }
+    }
+}
+
+impl Controls {
+    fn bindings(&self) -> [(Action, &KeyList); 22] {
+        [
+            (Action::Quit, &self.quit),
+            (Action::NormalMode, &self.normal_mode),
+            (Action::ScrollUp, &self.scroll_up),
+            (Action::ScrollDown, &self.scroll_down),
+            (Action::PageUp, &self.page_up),
+            (Action::PageDown, &self.page_down),
+            (Action::Copy, &self.copy),
+            (Action::CopyLine, &self.copy_line),
+            (Action::Reload, &self.reload),
+            (Action::ToggleRegex, &self.toggle_regex),
+            (Action::ToggleCase, &self.toggle_case),
+            (Action::CycleSort, &self.cycle_sort),
+            (Action::NextMatch, &self.next_match),
+            (Action::PreviousMatch, &self.previous_match),
+            (Action::Details, &self.details),
+            (Action::ClearSearch, &self.clear_search),
+            (Action::ToggleCompact, &self.toggle_compact),
+            (Action::JumpToCategory, &self.jump_to_category),
+            (Action::ReverseSort, &self.reverse_sort),
+            (Action::HistoryPrevious, &self.history_previous),
+            (Action::HistoryNext, &self.history_next),
+            (Action::EditConfig, &self.edit_config),
+        ]
+    }
+
+    // Finds the action bound to `key`. Bare characters are only honored in
+    // normal mode, since in insert mode they type into the search.
+    // Clearing the search only applies in insert mode, so its default
+    // Ctrl+u still pages up in normal mode.
+    pub fn action_for(&self, key: &KeyEvent, mode: Mode) -> Option<Action> {
+        self.bindings().into_iter().find_map(|(action, keys)| {
+            if action == Action::ClearSearch && mode == Mode::Normal {
+                return None;
+            }
+            keys.0
+                .iter()
+                .any(|binding| {
+                    binding.matches(key) && (mode == Mode::Normal || !binding.is_plain_char())
+                })
+                .then_some(action)
+        })
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub keybinds: Vec<Keybind>,
+    #[serde(default)]
+    pub search: SearchMode,
+    #[serde(default, deserialize_with = "deserialize_theme")]
+    pub theme: Theme,
+    #[serde(default)]
+    pub controls: Controls,
+    #[serde(default)]
+    pub layout: LayoutSettings,
+    // Files whose keybinds are appended, relative to this config's directory
+    #[serde(default)]
+    pub include: Vec<PathBuf>,
+    // Set when the configs read had no keybinds, so the built-in ones were
+    // filled in
+    #[serde(skip)]
+    pub no_keybinds: bool,
+    #[serde(default)]
+    pub sort: SortMode,
+    #[serde(default)]
+    pub sort_reverse: bool,
+    #[serde(default)]
+    pub footer: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    #[serde(default)]
+    pub placeholder: Option<String>,
+    // Text in the title row, instead of the translated "Keybinds"
+    #[serde(default)]
+    pub title: Option<String>,
+    // Whether to show the title row with the mode, sort and match count
+    #[serde(default = "default_true")]
+    pub title_bar: bool,
+    #[serde(default)]
+    pub lang: Option<String>,
+    #[serde(default)]
+    pub columns: usize,
+    #[serde(default)]
+    pub name_align: NameAlign,
+    // Width of the key column for left-aligned names; 0 fits the longest
+    #[serde(default)]
+    pub key_width: usize,
+    #[serde(default = "default_true")]
+    pub restore_query: bool,
+    #[serde(default)]
+    pub copy_command: Option<String>,
+    // Blank cells around the whole UI
+    #[serde(default = "default_margin")]
+    pub margin: u16,
+    // Blank line after each row of entries and category heading
+    #[serde(default = "default_true")]
+    pub spacing: bool,
+    #[serde(default = "default_true")]
+    pub descriptions: bool,
+    // One line per entry: no descriptions or spacing
+    #[serde(default)]
+    pub compact: bool,
+    // Up at the first entry goes to the last, and Down at the last to the first
+    #[serde(default)]
+    pub wrap: bool,
+    // Count copies and launches in the state file for the frequent sort
+    #[serde(default)]
+    pub track_usage: bool,
+    // Match letters regardless of accents, e.g. "cafe" finds "Café"
+    #[serde(default)]
+    pub fold_diacritics: bool,
+    // Entries moved by each Up or Down press, and lines by each wheel notch
+    #[serde(default = "default_scroll_step")]
+    pub scroll_step: usize,
+    // Lines of the previous screen still shown after PageUp or PageDown
+    #[serde(default)]
+    pub page_overlap: u16,
+    // Keep the selection on the only match left by a query
+    #[serde(default)]
+    pub auto_select_single: bool,
+    // With `auto_select_single`, act on that match as if Enter was pressed
+    #[serde(default)]
+    pub auto_accept_single: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+pub fn default_margin() -> u16 {
+    1
+}
+
+pub fn default_scroll_step() -> usize {
+    1
+}
+
+impl Config {
+    // Falls back to the built-in keybinds for a config without any, likely
+    // a mistake, and marks it so the menu can say so
+    fn fill_empty_keybinds(&mut self) {
+        if self.keybinds.is_empty() {
+            self.keybinds = default_keybinds();
+            self.no_keybinds = true;
+        }
+    }
+
+    // Drops entries with `enabled = false`
+    pub fn drop_disabled(&mut self) {
+        self.keybinds.retain(|item| item.enabled != Some(false));
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            keybinds: default_keybinds(),
+            search: SearchMode::default(),
+            theme: Theme::default(),
+            controls: Controls::default(),
+            layout: LayoutSettings::default(),
+            include: Vec::new(),
+            no_keybinds: false,
+            sort: SortMode::default(),
+            sort_reverse: false,
+            footer: false,
+            case_sensitive: false,
+            placeholder: None,
+            title: None,
+            title_bar: true,
+            lang: None,
+            columns: 0,
+            name_align: NameAlign::default(),
+            key_width: 0,
+            restore_query: true,
+            copy_command: None,
+            margin: default_margin(),
+            spacing: true,
+            descriptions: true,
+            compact: false,
+            wrap: false,
+            track_usage: false,
+            fold_diacritics: false,
+            scroll_step: default_scroll_step(),
+            page_overlap: 0,
+            auto_select_single: false,
+            auto_accept_single: false,
+        }
+    }
+}
+
+// Whether letters go to the search box or act as navigation commands
+#[derive(Clone, Copy, PartialEq)]
+pub enum Mode {
+    Insert,
+    Normal,
+}
+
+// Where the config comes from: the usual files, or text piped to --stdin
+#[derive(Clone)]
+pub enum ConfigSource {
+    Files(Option<PathBuf>),
+    Stdin(String),
+}
+
+impl ConfigSource {
+    // The file to open for editing: the explicit config, else the first user
+    // config that exists, else where a new user config goes. Stdin has none.
+    pub fn editable_path(&self) -> Option<PathBuf> {
+        let ConfigSource::Files(explicit) = self else {
+            return None;
+        };
+        if let Some(path) = explicit {
+            return Some(path.clone());
+        }
+        let (user_paths, _) = config_candidates(None);
+        user_paths
+            .into_iter()
+            .find(|path| path.exists())
+            .or_else(|| xdg_config_path().map(|dir| dir.join("nebula-keybind-menu/config.toml")))
+    }
+
+    // Loads the config, returning defaults and the error if it's unusable
+    pub fn load(&self) -> (Config, Option<String>) {
+        match self {
+            ConfigSource::Files(explicit) => load_config(explicit.as_deref()),
+            ConfigSource::Stdin(contents) if contents.trim().is_empty() => {
+                (Config::default(), None)
+            }
+            ConfigSource::Stdin(contents) => {
+                // Nothing to go by but the text itself.
+                let json = contents.trim_start().starts_with('{');
+                match parse_config(contents, json, "<stdin>") {
+                    Ok(mut config) => {
+                        config.fill_empty_keybinds();
+                        (config, None)
+                    }
+                    Err(err) => (Config::default(), Some(err)),
+                }
+            }
+        }
+    }
+}
+
+// Loads the explicit config, or the user config layered over the system
+// one, or returns defaults. Also returns the first error hit while reading
+// a config that exists.
+pub fn load_config(explicit: Option<&Path>) -> (Config, Option<String>) {
+    let mut first_error = None;
+    let mut trace = Vec::new();
+    let (user_paths, system_paths) = config_candidates(explicit);
+    let user = load_layer(&user_paths, &mut first_error, &mut trace);
+    let system = load_layer(&system_paths, &mut first_error, &mut trace);
+    let mut config = match (user, system) {
+        // The user's settings win; their keybinds override the system's.
+        (Some(mut user), Some(system)) => {
+            user.keybinds = merge_keybinds(system.keybinds, user.keybinds);
+            user
+        }
+        (Some(config), None) | (None, Some(config)) => config,
+        (None, None) => Config::default(),
+    };
+    config.fill_empty_keybinds();
+    config.keybinds.retain(|item| !item.hidden);
+    // Sources only help tell files apart, so one file leaves them unset.
+    let first = config.keybinds.first().and_then(|item| item.source.clone());
+    if config.keybinds.iter().all(|item| item.source == first) {
+        for item in &mut config.keybinds {
+            item.source = None;
+        }
+    }
+    (config, first_error)
+}
+
+// What loading made of one file, as listed by --print-path
+pub enum PathStatus {
+    // Read and used; the candidates after it in its layer aren't read
+    Used,
+    Missing,
+    // Read and used for its settings, but without keybinds
+    Empty,
+    Failed(String),
+    // Not read, as an earlier candidate was used
+    Skipped,
+    // A config.d snippet appended to the file before it
+    Snippet,
+    // A file named by an `include`, appended after the file naming it
+    Include,
+}
+
+pub struct TracedPath {
+    pub path: PathBuf,
+    pub status: PathStatus,
+}
+
+// Every file `load_config` would look at for the user and system layers, in
+// the order it looks, and what it made of each
+pub fn trace_config(explicit: Option<&Path>) -> (Vec<TracedPath>, Vec<TracedPath>) {
+    let (user_paths, system_paths) = config_candidates(explicit);
+    let (mut user, mut system) = (Vec::new(), Vec::new());
+    load_layer(&user_paths, &mut None, &mut user);
+    load_layer(&system_paths, &mut None, &mut system);
+    (user, system)
+}
+
+// Loads the first config among `paths` that parses, with its includes and
+// config.d snippets. It's used even without keybinds, for its settings.
+// What happened to each path goes into `trace`.
+fn load_layer(
+    paths: &[PathBuf],
+    first_error: &mut Option<String>,
+    trace: &mut Vec<TracedPath>,
+) -> Option<Config> {
+    let mut traced = |path: &Path, status| {
+        trace.push(TracedPath {
+            path: path.to_path_buf(),
+            status,
+        })
+    };
+    for (index, path) in paths.iter().enumerate() {
+        match read_config(path) {
+            Ok(Some(mut config)) => {
+                for item in &mut config.keybinds {
+                    item.source = Some(path.clone());
+                }
+                // Included files, then config.d snippets, each traced after
+                // the config itself.
+                let mut snippets = Vec::new();
+                let mut includes = Includes {
+                    chain: vec![canonical(path)],
+                    seen: Vec::new(),
+                };
+                let mut errors = Vec::new();
+                merge_includes(
+                    &mut config.keybinds,
+                    path,
+                    &config.include,
+                    &mut includes,
+                    &mut errors,
+                    &mut snippets,
+                );
+                if let Some(dir) = path.parent() {
+                    errors.extend(merge_snippets(
+                        &mut config,
+                        &dir.join("config.d"),
+                        &mut includes,
+                        &mut snippets,
+                    ));
+                }
+                if let Some(err) = errors.into_iter().next() {
+                    first_error.get_or_insert(err);
+                }
+                let status = if config.keybinds.is_empty() {
+                    PathStatus::Empty
+                } else {
+                    PathStatus::Used
+                };
+                traced(path, status);
+                trace.append(&mut snippets);
+                for path in &paths[index + 1..] {
+                    trace.push(TracedPath {
+                        path: path.clone(),
+                        status: PathStatus::Skipped,
+                    });
+                }
+                return Some(config);
+            }
+            Ok(None) => traced(path, PathStatus::Missing),
+            Err(err) => {
+                traced(path, PathStatus::Failed(err.clone()));
+                first_error.get_or_insert(err);
+            }
+        }
+    }
+    None
+}
+
+// Applies user keybinds over the system ones: an entry with the same name
+// replaces the system entry in place, and anything else is appended.
+// Hidden entries are kept here so the caller can drop them afterwards.
+fn merge_keybinds(mut base: Vec<Keybind>, overrides: Vec<Keybind>) -> Vec<Keybind> {
+    for item in overrides {
+        match base.iter_mut().find(|existing| existing.name == item.name) {
+            Some(existing) => *existing = item,
+            None => base.push(item),
+        }
+    }
+    base
+}
+
+// Keybinds from a config.d snippet or an included file; other settings
+// belong in the main config
+#[derive(Deserialize)]
+struct Snippet {
+    #[serde(default)]
+    keybinds: Vec<Keybind>,
+    #[serde(default)]
+    include: Vec<PathBuf>,
+}
+
+// Reads a snippet, as JSON if the name ends in .json and TOML otherwise.
+// Returns `Ok(None)` if it doesn't exist.
+fn read_snippet(path: &Path) -> Result<Option<Snippet>, String> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(format!("{}: {}", path.display(), err)),
+    };
+    if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&contents).map_err(|err| format!("{}: {}", path.display(), err))
+    } else {
+        toml::from_str(&contents).map_err(|err| format!("{}: {}", path.display(), err.message()))
+    }
+    .map(Some)
+}
+
+// The files included so far while loading one layer. `chain` runs from the
+// config down to the file being read, to spot cycles; `seen` has every file
+// read, so one included from two places is only merged once.
+#[derive(Default)]
+struct Includes {
+    chain: Vec<PathBuf>,
+    seen: Vec<PathBuf>,
+}
+
+// The path with symlinks and `..` resolved, so one file is always spelled
+// the same way
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+// Appends the keybinds of the files `from` includes, in the order listed,
+// each followed by the files it includes in turn. Paths are relative to the
+// directory of `from`. Missing files and cycles are skipped with an error.
+fn merge_includes(
+    keybinds: &mut Vec<Keybind>,
+    from: &Path,
+    include: &[PathBuf],
+    includes: &mut Includes,
+    errors: &mut Vec<String>,
+    trace: &mut Vec<TracedPath>,
+) {
+    let dir = from.parent().unwrap_or(Path::new(""));
+    for path in include.iter().map(|path| dir.join(path)) {
+        let resolved = canonical(&path);
+        let status = if includes.chain.contains(&resolved) {
+            let err = format!(
+                "{}: including {} again would loop, so it was skipped",
+                from.display(),
+                path.display()
+            );
+            errors.push(err.clone());
+            PathStatus::Failed(err)
+        } else if includes.seen.contains(&resolved) {
+            PathStatus::Skipped
+        } else {
+            match read_snippet(&path) {
+                Ok(Some(snippet)) => {
+                    includes.seen.push(resolved.clone());
+                    keybinds.extend(snippet.keybinds.into_iter().map(|item| Keybind {
+                        source: Some(path.clone()),
+                        ..item
+                    }));
+                    trace.push(TracedPath {
+                        path: path.clone(),
+                        status: PathStatus::Include,
+                    });
+                    includes.chain.push(resolved);
+                    merge_includes(keybinds, &path, &snippet.include, includes, errors, trace);
+                    includes.chain.pop();
+                    continue;
+                }
+                Ok(None) => {
+                    errors.push(format!(
+                        "{}: included file {} not found, so it was skipped",
+                        from.display(),
+                        path.display()
+                    ));
+                    PathStatus::Missing
+                }
+                Err(err) => {
+                    errors.push(err.clone());
+                    PathStatus::Failed(err)
+                }
+            }
+        };
+        trace.push(TracedPath { path, status });
+    }
+}
+
+// Appends the keybinds of every *.toml file in `dir`, in filename order.
+// All entries are kept, even if they repeat keys from earlier files.
+// Returns an error for each snippet that couldn't be read.
+fn merge_snippets(
+    config: &mut Config,
+    dir: &Path,
+    includes: &mut Includes,
+    trace: &mut Vec<TracedPath>,
+) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    paths.sort();
+
+    let mut errors = Vec::new();
+    for path in paths {
+        // Already merged through an `include`
+        let resolved = canonical(&path);
+        if includes.seen.contains(&resolved) {
+            trace.push(TracedPath {
+                path,
+                status: PathStatus::Skipped,
+            });
+            continue;
+        }
+        match read_snippet(&path) {
+            Ok(None) => {}
+            Ok(Some(snippet)) => {
+                includes.seen.push(resolved.clone());
+                config
+                    .keybinds
+                    .extend(snippet.keybinds.into_iter().map(|item| Keybind {
+                        source: Some(path.clone()),
+                        ..item
+                    }));
+                trace.push(TracedPath {
+                    path: path.clone(),
+                    status: PathStatus::Snippet,
+                });
+                includes.chain.push(resolved);
+                merge_includes(
+                    &mut config.keybinds,
+                    &path,
+                    &snippet.include,
+                    includes,
+                    &mut errors,
+                    trace,
+                );
+                includes.chain.pop();
+            }
+            Err(err) => {
+                trace.push(TracedPath {
+                    path,
+                    status: PathStatus::Failed(err.clone()),
+                });
+                errors.push(err);
+            }
+        }
+    }
+    errors
+}
+
+// User and system config paths to try, each in order. An explicit path
+// replaces both; $NEBULA_KEYBIND_MENU_CONFIG is tried before the user
+// config. Within a directory, config.toml takes precedence over config.json.
+fn config_candidates(explicit: Option<&Path>) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    if let Some(path) = explicit {
+        return (vec![path.to_path_buf()], Vec::new());
+    }
+    let in_dir = |dir: PathBuf| [dir.join("config.toml"), dir.join("config.json")];
+    let from_env = std::env::var_os("NEBULA_KEYBIND_MENU_CONFIG")
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from);
+    let user_dir = xdg_config_path().map(|path| path.join("nebula-keybind-menu"));
+    let user = from_env
+        .into_iter()
+        .chain(user_dir.into_iter().flat_map(in_dir))
+        .collect();
+    let system = system_config_dirs(std::env::var_os("XDG_CONFIG_DIRS").as_deref())
+        .into_iter()
+        .flat_map(in_dir)
+        .collect();
+    (user, system)
+}
+
+// System config directories in the order they're tried: `nebula-keybind-menu`
+// in each absolute entry of a colon-separated $XDG_CONFIG_DIRS (or /etc/xdg
+// when it's unset or empty), then the install prefix
+pub fn system_config_dirs(xdg_config_dirs: Option<&OsStr>) -> Vec<PathBuf> {
+    let dirs = xdg_config_dirs
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or(OsStr::new("/etc/xdg"));
+    std::env::split_paths(dirs)
+        .filter(|dir| dir.is_absolute())
+        .map(|dir| dir.join("nebula-keybind-menu"))
+        .chain([PathBuf::from("/usr/share/nebula-keybind-menu")])
+        .collect()
+}
+
+// Reads and parses one config file, returning `Ok(None)` if it doesn't exist
+fn read_config(path: &Path) -> Result<Option<Config>, String> {
+    let json = path.extension().is_some_and(|ext| ext == "json");
+    let stamp = std::fs::metadata(path)
+        .ok()
+        .and_then(|meta| ConfigCache::stamp(&meta));
+    if let Some(config) = stamp
+        .filter(|_| !json)
+        .and_then(|stamp| ConfigCache::load(path, stamp))
+    {
+        return Ok(Some(config));
+    }
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(format!("{}: {}", path.display(), err)),
+    };
+    let config = parse_config(&contents, json, &path.display().to_string())?;
+    if let Some(stamp) = stamp.filter(|_| !json) {
+        ConfigCache::store(path, stamp, &contents);
+    }
+    Ok(Some(config))
+}
+
+// A TOML config converted to JSON, which parses much faster, in
+// $XDG_CACHE_HOME/nebula-keybind-menu. It's used while the source file's
+// size and modification time still match.
+#[derive(Deserialize, Serialize)]
+struct ConfigCache {
+    source: PathBuf,
+    stamp: (Duration, u64),
+    config: serde_json::Value,
+}
+
+impl ConfigCache {
+    // The source's modification time and size
+    fn stamp(meta: &std::fs::Metadata) -> Option<(Duration, u64)> {
+        let modified = meta.modified().ok()?;
+        let since_epoch = modified.duration_since(std::time::UNIX_EPOCH).ok()?;
+        Some((since_epoch, meta.len()))
+    }
+
+    // One file per source, named after a hash of its path
+    fn path(source: &Path) -> Option<PathBuf> {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::hash::DefaultHasher::new();
+        source.hash(&mut hasher);
+        xdg_cache_path().map(|path| {
+            path.join("nebula-keybind-menu")
+                .join(format!("{:016x}.json", hasher.finish()))
+        })
+    }
+
+    // Anything wrong with the cache just means parsing the source again
+    fn load(source: &Path, stamp: (Duration, u64)) -> Option<Config> {
+        let contents = std::fs::read_to_string(Self::path(source)?).ok()?;
+        let cache: ConfigCache = serde_json::from_str(&contents).ok()?;
+        if cache.source != source || cache.stamp != stamp {
+            return None;
+        }
+        Config::deserialize(cache.config).ok()
+    }
+
+    // Best effort: a cache that can't be written is skipped
+    fn store(source: &Path, stamp: (Duration, u64), contents: &str) {
+        let Some(path) = Self::path(source) else {
+            return;
+        };
+        let Ok(config) = toml::from_str(contents) else {
+            return;
+        };
+        let cache = ConfigCache {
+            source: source.to_path_buf(),
+            stamp,
+            config,
+        };
+        if let (Some(dir), Ok(contents)) = (path.parent(), serde_json::to_string(&cache)) {
+            let _ = std::fs::create_dir_all(dir).and_then(|()| std::fs::write(path, contents));
+        }
+    }
+}
+
+// Parses config text as JSON or TOML. Errors are prefixed with `name`.
+pub fn parse_config(contents: &str, json: bool, name: &str) -> Result<Config, String> {
+    if json {
+        return serde_json::from_str(contents).map_err(|err| format!("{}: {}", name, err));
+    }
+    toml::from_str(contents).map_err(|err| {
+        // Point at the offending line so the message fits on one row.
+        match err.span() {
+            Some(span) => {
+                let line = contents[..span.start].matches('\n').count() + 1;
+                format!("{}:{}: {}", name, line, err.message())
+            }
+            None => format!("{}: {}", name, err.message()),
+        }
+    })
+}
+
+// Fallback default keybinds
+pub fn default_keybinds() -> Vec<Keybind> {
+    vec![
+        Keybind {
+            keys: "SUPER + SPACE".to_string(),
+            name: "Launcher".to_string(),
+            desc: "Open app launcher".to_string(),
+            ..Default::default()
+        },
+        Keybind {
+            keys: "SUPER + B".to_string(),
+            name: "Web Browser".to_string(),
+            desc: "Open default browser".to_string(),
+            ..Default::default()
+        },
+        Keybind {
+            keys: "SUPER + ENTER".to_string(),
+            name: "Terminal".to_string(),
+            desc: "Open terminal".to_string(),
+            ..Default::default()
+        },
+        Keybind {
+            keys: "SUPER + Q".to_string(),
+            name: "Close Window".to_string(),
+            desc: "Close focused window".to_string(),
+            ..Default::default()
+        },
+    ]
+}
+
+// Returns the XDG config path, if available.
+pub fn xdg_config_path() -> Option<PathBuf> {
+    xdg_base_dir("XDG_CONFIG_HOME", ".config")
+}
+
+// Returns the XDG cache path, if available.
+fn xdg_cache_path() -> Option<PathBuf> {
+    xdg_base_dir("XDG_CACHE_HOME", ".cache")
+}
+
+// Returns the XDG state path, if available.
+pub fn xdg_state_path() -> Option<PathBuf> {
+    xdg_base_dir("XDG_STATE_HOME", ".local/state")
+}
+
+// The directory in the XDG variable `var`, or `fallback` under $HOME. The
+// spec says to ignore a value that isn't an absolute path, empty included.
+fn xdg_base_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    let path = std::env::var_os(var).map(PathBuf::from);
+    if let Some(path) = path.filter(|path| path.is_absolute()) {
+        return Some(path);
+    }
+    let home = std::env::var_os("HOME").filter(|home| !home.is_empty())?;
+    Some(PathBuf::from(home).join(fallback))
+}
+
+// Normalizes a key combo so "super+q" and "SUPER + Q" compare equal.
+// Modifiers are sorted; the final key stays last.
+pub fn normalize_keys(keys: &str) -> String {
+    keys.split(SEQUENCE_SEPARATOR)
+        .map(|step| {
+            let mut parts: Vec<String> = step
+                .split('+')
+                .map(|part| part.trim().to_uppercase())
+                .filter(|part| !part.is_empty())
+                .collect();
+            if let Some((_, modifiers)) = parts.split_last_mut() {
+                modifiers.sort();
+            }
+            parts.join("+")
+        })
+        .collect::<Vec<_>>()
+        .join(SEQUENCE_SEPARATOR)
+}
+
+// Groups keybinds sharing the same key combo, in config order.
+// Returns the combo as first written and the names of every entry using it.
+pub fn find_duplicates(keybinds: &[Keybind]) -> Vec<(&str, Vec<&str>)> {
+    let mut groups: Vec<(String, &str, Vec<&str>)> = Vec::new();
+    for item in keybinds {
+        let normalized = normalize_keys(&item.keys);
+        match groups.iter_mut().find(|(keys, _, _)| *keys == normalized) {
+            Some((_, _, names)) => names.push(&item.name),
+            None => groups.push((normalized, &item.keys, vec![&item.name])),
+        }
+    }
+    groups
+        .into_iter()
+        .filter(|(_, _, names)| names.len() > 1)
+        .map(|(_, keys, names)| (keys, names))
+        .collect()
+}
+
+// How well a keybind matched a query, with the char positions to highlight
+#[derive(Clone, Default)]
+pub struct Score {
+    pub value: i64,
+    pub key_hits: Vec<usize>,
+    pub name_hits: Vec<usize>,
+    pub desc_hits: Vec<usize>,
+}
+
+// A keybind that matched the query
+pub struct Match {
+    // Position of the keybind in the searched slice
+    pub index: usize,
+    pub score: Score,
+}
+
+// How a query is matched against the keybinds and the matches ordered
+#[derive(Clone, Copy, Default)]
+pub struct SearchOptions<'a> {
+    pub mode: SearchMode,
+    pub case_sensitive: bool,
+    // The compiled query for regex mode, where nothing matches without it
+    pub regex: Option<&'a Regex>,
+    pub sort: SortMode,
+    pub reverse: bool,
+    // How much each keybind has been used, by name, for the frequent sort
+    pub usage: Option<&'a HashMap<&'a str, f64>>,
+    // Ignore accents outside regex search, so "cafe" finds "Café"
+    pub fold_diacritics: bool,
+}
+
+// Returns the keybinds matching `query`, in the order they should be shown
+pub fn filter_keybinds(items: &[Keybind], query: &str, options: &SearchOptions) -> Vec<Match> {
+    let query = split_query(query);
+    let mut matches: Vec<Match> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(index, item)| {
+            let score = match_keybind(item, &query, options)?;
+            Some(Match { index, score })
+        })
+        .collect();
+    sort_matches(items, &mut matches, options);
+    matches
+}
+
+// Scores one keybind against a query, or returns None if its filters or
+// text rule it out. With no text every keybind that passes the filters
+// matches with a zero score.
+pub fn match_keybind(item: &Keybind, query: &Query, options: &SearchOptions) -> Option<Score> {
+    let in_category = query.categories.is_empty()
+        || item.category.as_ref().is_some_and(|category| {
+            let category = category.to_lowercase();
+            query
+                .categories
+                .iter()
+                .any(|wanted| category.contains(&wanted.to_lowercase()))
+        });
+    let has_tags = query
+        .tags
+        .iter()
+        .all(|tag| item.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)));
+    if !in_category || !has_tags {
+        return None;
+    }
+    // Keys, name and description first, the order their hits are kept in
+    let fields: Vec<&str> = [
+        item.keys.as_str(),
+        item.name.as_str(),
+        item.desc.as_str(),
+        item.category.as_deref().unwrap_or_default(),
+    ]
+    .into_iter()
+    .chain(item.aliases.iter().map(String::as_str))
+    .collect();
+    let find = |needle: &str, field: &str| {
+        folded_match(needle, field, options.fold_diacritics, |needle, field| {
+            substring_match(needle, field, options.case_sensitive).map(|hits| (0, hits))
+        })
+        .map(|(_, hits)| hits)
+    };
+    // Exclusions win over everything else, even a phrase asking for the
+    // same text.
+    if query
+        .excludes
+        .iter()
+        .any(|term| fields.iter().any(|field| find(term, field).is_some()))
+    {
+        return None;
+    }
+    let mut phrase_hits: [Vec<usize>; 3] = Default::default();
+    for phrase in &query.phrases {
+        let found: Vec<_> = fields.iter().map(|field| find(phrase, field)).collect();
+        if found.iter().all(Option::is_none) {
+            return None;
+        }
+        // Only keys, name and description show highlights.
+        for (hits, found) in phrase_hits.iter_mut().zip(found) {
+            hits.extend(found.unwrap_or_default());
+        }
+    }
+    // Highlighting looks hits up by binary search, so keep them sorted.
+    let merge = |field: Option<(i64, Vec<usize>)>, mut hits: Vec<usize>| {
+        hits.extend(field.map(|(_, hits)| hits).unwrap_or_default());
+        hits.sort_unstable();
+        hits.dedup();
+        hits
+    };
+    let [phrase_keys, phrase_name, phrase_desc] = phrase_hits;
+    let text = query.text.as_str();
+    if text.is_empty() {
+        return Some(Score {
+            value: 0,
+            key_hits: merge(None, phrase_keys),
+            name_hits: merge(None, phrase_name),
+            desc_hits: merge(None, phrase_desc),
+        });
+    }
+
+    let keys = match_field(text, &item.keys, options);
+    let name = match_field(text, &item.name, options);
+    let desc = match_field(text, &item.desc, options);
+    let category = item
+        .category
+        .as_ref()
+        .and_then(|category| match_field(text, category, options));
+    let alias = item
+        .aliases
+        .iter()
+        .filter_map(|alias| match_field(text, alias, options))
+        .map(|(score, _)| score)
+        .max();
+    // A hit in the keys ranks a little below the same hit in the name or
+    // description, which say what the keybind does, and a hit in just the
+    // category below both. Aliases stand in for the name, just below it.
+    let value = [
+        keys.as_ref().map(|(score, _)| score - KEY_MATCH_PENALTY),
+        name.as_ref().map(|(score, _)| *score),
+        desc.as_ref().map(|(score, _)| *score),
+        category.map(|(score, _)| score - CATEGORY_MATCH_PENALTY),
+        alias.map(|score| score - ALIAS_MATCH_PENALTY),
+    ]
+    .into_iter()
+    .flatten()
+    .max()?;
+    Some(Score {
+        value,
+        key_hits: merge(keys, phrase_keys),
+        name_hits: merge(name, phrase_name),
+        desc_hits: merge(desc, phrase_desc),
+    })
+}
+
+// Applies the sort mode and direction, then groups by category. Sorts
+// are stable, so ties keep config order (reversed along with the rest).
+fn sort_matches(items: &[Keybind], matches: &mut [Match], options: &SearchOptions) {
+    match options.sort {
+        SortMode::Relevance => matches.sort_by_key(|m| std::cmp::Reverse(m.score.value)),
+        SortMode::Config => {}
+        SortMode::Name => matches.sort_by_cached_key(|m| items[m.index].name.to_lowercase()),
+        SortMode::Keys => matches.sort_by_cached_key(|m| items[m.index].keys.to_lowercase()),
+        SortMode::Frequent => {
+            let weight = |m: &Match| {
+                options
+                    .usage
+                    .and_then(|usage| usage.get(items[m.index].name.as_str()))
+                    .copied()
+                    .unwrap_or(0.0)
+            };
+            matches.sort_by(|a, b| weight(b).total_cmp(&weight(a)));
+        }
+    }
+    if options.reverse {
+        matches.reverse();
+    }
+    group_by_category(items, matches);
+}
+
+// Matches `query` against one field using the search mode
+fn match_field(query: &str, target: &str, options: &SearchOptions) -> Option<(i64, Vec<usize>)> {
+    let fold = options.fold_diacritics;
+    match options.mode {
+        SearchMode::Fuzzy => folded_match(query, target, fold, |query, target| {
+            fuzzy_match(query, target, options.case_sensitive)
+        }),
+        SearchMode::Substring => folded_match(query, target, fold, |query, target| {
+            substring_match(query, target, options.case_sensitive).map(|hits| (0, hits))
+        }),
+        SearchMode::Regex => regex_match(options.regex?, target).map(|hits| (0, hits)),
+    }
+}
+
+// Runs `matcher` with the accents stripped from `query` and `target` when
+// `fold` is set. The hits still point at the chars of the original target.
+fn folded_match(
+    query: &str,
+    target: &str,
+    fold: bool,
+    matcher: impl Fn(&str, &str) -> Option<(i64, Vec<usize>)>,
+) -> Option<(i64, Vec<usize>)> {
+    if !fold || query.is_ascii() && target.is_ascii() {
+        return matcher(query, target);
+    }
+    let (query, _) = fold_diacritics(query);
+    let (folded, origins) = fold_diacritics(target);
+    let (score, hits) = matcher(&query, &folded)?;
+    let mut hits: Vec<usize> = hits.into_iter().map(|hit| origins[hit]).collect();
+    hits.dedup();
+    Some((score, hits))
+}
+
+// Decomposes `text` and drops the combining marks, turning "Café" into
+// "Cafe". Also returns, for each char left, the index of the char in `text`
+// it came from.
+fn fold_diacritics(text: &str) -> (String, Vec<usize>) {
+    let mut folded = String::with_capacity(text.len());
+    let mut origins = Vec::with_capacity(text.len());
+    for (index, ch) in text.chars().enumerate() {
+        for part in std::iter::once(ch).nfd().filter(|&c| !is_combining_mark(c)) {
+            folded.push(part);
+            origins.push(index);
+        }
+    }
+    (folded, origins)
+}
+
+// Orders matches by category (in config order), keeping the existing
+// order within each category. Uncategorized items come first.
+fn group_by_category(items: &[Keybind], matches: &mut [Match]) {
+    let mut categories: Vec<&str> = Vec::new();
+    for category in items.iter().filter_map(|item| item.category.as_deref()) {
+        if !categories.contains(&category) {
+            categories.push(category);
+        }
+    }
+    if categories.is_empty() {
+        return;
+    }
+    matches.sort_by_key(|m| {
+        items[m.index]
+            .category
+            .as_deref()
+            .and_then(|category| categories.iter().position(|c| *c == category))
+            .map_or(0, |position| position + 1)
+    });
+}
+
+// The keybind name closest to `query` by edit distance, ignoring case, if
+// it's close enough to be what a mistyped query meant
+pub fn closest_name<'a>(items: &'a [Keybind], query: &str) -> Option<&'a str> {
+    let query = query.trim().to_lowercase();
+    let len = query.chars().count();
+    if len < 3 {
+        return None;
+    }
+    let limit = len / 3 + 1;
+    items
+        .iter()
+        .map(|item| (edit_distance(&query, &item.name.to_lowercase()), &item.name))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, name)| name.as_str())
+}
+
+// Levenshtein distance between two strings, counted in chars
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+// A search query split into its `tag:` and `cat:` filters, `"phrases"`,
+// `-exclusions` and free text
+pub struct Query<'a> {
+    pub tags: Vec<&'a str>,
+    pub categories: Vec<&'a str>,
+    // Each must appear literally somewhere in the keybind
+    pub phrases: Vec<&'a str>,
+    // None may appear anywhere in the keybind
+    pub excludes: Vec<&'a str>,
+    pub text: String,
+}
+
+// Whether `query` uses any operator, so plain queries can skip parsing
+fn has_operators(query: &str) -> bool {
+    query.contains("tag:")
+        || query.contains("cat:")
+        || query.contains('"')
+        || query
+            .split_whitespace()
+            .any(|word| word.len() > 1 && word.starts_with('-'))
+}
+
+// Pulls the operators out of a query. Without any the query is left exactly
+// as typed. A `"` starts a phrase only at the beginning of a word, and an
+// unclosed one runs to the end, so a phrase filters while it's being typed.
+// Empty phrases and a lone `-` are ignored and kept as text respectively.
+pub fn split_query(query: &str) -> Query<'_> {
+    let mut parsed = Query {
+        tags: Vec::new(),
+        categories: Vec::new(),
+        phrases: Vec::new(),
+        excludes: Vec::new(),
+        text: String::new(),
+    };
+    if !has_operators(query) {
+        parsed.text = query.to_string();
+        return parsed;
+    }
+    let mut words = Vec::new();
+    let mut rest = query.trim_start();
+    while !rest.is_empty() {
+        let (exclude, quoted) = match rest.strip_prefix("-\"") {
+            Some(quoted) => (true, Some(quoted)),
+            None => (false, rest.strip_prefix('"')),
+        };
+        if let Some(quoted) = quoted {
+            let (phrase, after) = quoted.split_once('"').unwrap_or((quoted, ""));
+            if !phrase.is_empty() {
+                if exclude {
+                    parsed.excludes.push(phrase);
+                } else {
+                    parsed.phrases.push(phrase);
+                }
+            }
+            rest = after.trim_start();
+            continue;
+        }
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let (word, after) = rest.split_at(end);
+        rest = after.trim_start();
+        if let Some(tag) = word.strip_prefix("tag:").filter(|tag| !tag.is_empty()) {
+            parsed.tags.push(tag);
+        } else if let Some(category) = word.strip_prefix("cat:").filter(|cat| !cat.is_empty()) {
+            parsed.categories.push(category);
+        } else if let Some(term) = word.strip_prefix('-').filter(|term| !term.is_empty()) {
+            parsed.excludes.push(term);
+        } else {
+            words.push(word);
+        }
+    }
+    parsed.text = words.join(" ");
+    parsed
+}
+
+// Finds every match of `regex` in `target`.
+// Returns the char positions covered by all matches.
+fn regex_match(regex: &Regex, target: &str) -> Option<Vec<usize>> {
+    let mut found = false;
+    let mut hits = Vec::new();
+    for m in regex.find_iter(target) {
+        found = true;
+        let start = target[..m.start()].chars().count();
+        let len = m.as_str().chars().count();
+        hits.extend(start..start + len);
+    }
+    if found {
+        Some(hits)
+    } else {
+        None
+    }
+}
+
+// Finds every case-insensitive occurrence of `query` in `target`.
+// Returns the char positions covered by all occurrences.
+fn substring_match(query: &str, target: &str, case_sensitive: bool) -> Option<Vec<usize>> {
+    let query: Vec<char> = query.chars().collect();
+    let target: Vec<char> = target.chars().collect();
+    if query.is_empty() || query.len() > target.len() {
+        return None;
+    }
+
+    let mut hits = Vec::new();
+    let mut start = 0;
+    while start + query.len() <= target.len() {
+        let window = &target[start..start + query.len()];
+        if window
+            .iter()
+            .zip(&query)
+            .all(|(&t, &q)| chars_eq(t, q, case_sensitive))
+        {
+            hits.extend(start..start + query.len());
+            start += query.len();
+        } else {
+            start += 1;
+        }
+    }
+    if hits.is_empty() {
+        None
+    } else {
+        Some(hits)
+    }
+}
+
+// Subtracted from a match in the keys, so name and description hits win ties
+const KEY_MATCH_PENALTY: i64 = 8;
+
+// Subtracted from a match in an alias, so the same words in a name win
+const ALIAS_MATCH_PENALTY: i64 = 4;
+
+// Subtracted from a match in the category, so entries that match
+// themselves come first
+const CATEGORY_MATCH_PENALTY: i64 = 16;
+
+const SCORE_MATCH: i64 = 16;
+const BONUS_CONSECUTIVE: i64 = 8;
+const BONUS_BOUNDARY: i64 = 10;
+const PENALTY_GAP_START: i64 = 3;
+const PENALTY_GAP_EXTENSION: i64 = 1;
+
+// Scores `query` as a case-insensitive subsequence of `target`, fzf style.
+// Returns the score and the char positions in `target` that matched.
+fn fuzzy_match(query: &str, target: &str, case_sensitive: bool) -> Option<(i64, Vec<usize>)> {
+    let query: Vec<char> = query.chars().collect();
+    let target: Vec<char> = target.chars().collect();
+    if query.is_empty() {
+        return Some((0, Vec::new()));
+    }
+
+    // Forward pass: find where the leftmost complete match ends.
+    let mut qi = 0;
+    let mut end = None;
+    for (ti, &tc) in target.iter().enumerate() {
+        if chars_eq(tc, query[qi], case_sensitive) {
+            qi += 1;
+            if qi == query.len() {
+                end = Some(ti);
+                break;
+            }
+        }
+    }
+    let end = end?;
+
+    // Backward pass: tighten the start of the match window.
+    let mut qi = query.len();
+    let mut start = end;
+    for ti in (0..=end).rev() {
+        if chars_eq(target[ti], query[qi - 1], case_sensitive) {
+            qi -= 1;
+            if qi == 0 {
+                start = ti;
+                break;
+            }
+        }
+    }
+
+    let mut positions = Vec::with_capacity(query.len());
+    let mut score = 0;
+    let mut qi = 0;
+    let mut prev: Option<usize> = None;
+    for (ti, &tc) in target.iter().enumerate().take(end + 1).skip(start) {
+        if qi == query.len() {
+            break;
+        }
+        if !chars_eq(tc, query[qi], case_sensitive) {
+            continue;
+        }
+        score += SCORE_MATCH;
+        if is_word_boundary(&target, ti) {
+            score += BONUS_BOUNDARY;
+        }
+        match prev {
+            Some(p) if p + 1 == ti => score += BONUS_CONSECUTIVE,
+            Some(p) => {
+                score -= PENALTY_GAP_START + PENALTY_GAP_EXTENSION * (ti - p - 2) as i64;
+            }
+            None => {}
+        }
+        positions.push(ti);
+        prev = Some(ti);
+        qi += 1;
+    }
+    Some((score, positions))
+}
+
+fn chars_eq(a: char, b: char, case_sensitive: bool) -> bool {
+    a == b || !case_sensitive && a.to_lowercase().eq(b.to_lowercase())
+}
+
+// True if the char at `index` starts a word (after a separator or a camelCase hump)
+fn is_word_boundary(chars: &[char], index: usize) -> bool {
+    if index == 0 {
+        return true;
+    }
+    let prev = chars[index - 1];
+    let curr = chars[index];
+    !prev.is_alphanumeric() || (prev.is_lowercase() && curr.is_uppercase())
+}
+
+// Width math for drawing. Sizes come in as usize, so content longer than a
+// terminal is wide can't wrap around, and results never exceed the space
+// they're given.
+
+// Dashes for each side of text `text_width` cells wide, centered in `width`
+// cells with a space on either side. None when there's no room for at least
+// one dash a side.
+pub fn dash_padding(width: usize, text_width: usize) -> Option<(usize, usize)> {
+    let total = width.checked_sub(text_width.checked_add(2)?)?;
+    if total < 2 {
+        return None;
+    }
+    let left = total / 2;
+    Some((left, total - left))
+}
+
+// A `width` by `height` rect centered in `area`, shrunk to fit inside it
+pub fn centered_rect(area: Rect, width: usize, height: usize) -> Rect {
+    let width = u16::try_from(width).unwrap_or(u16::MAX).min(area.width);
+    let height = u16::try_from(height).unwrap_or(u16::MAX).min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+// Where an entry's name goes in its cell, relative to the keys
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NamePlacement {
+    // On the keys' line, after this many cells of padding
+    SameLine(usize),
+    // On its own line below the keys, indented this many cells
+    Below(usize),
+}
+
+// Places a name `name_width` cells wide after keys `keys_width` wide, in a
+// cell `width` wide. The name lines up at `key_column` if given, and is
+// right-aligned otherwise. Names that don't fit next to the keys go on the
+// line below, indented the same way as far as they fit.
+pub fn place_name(
+    keys_width: usize,
+    name_width: usize,
+    width: usize,
+    key_column: Option<usize>,
+) -> NamePlacement {
+    let name_start = match key_column {
+        Some(column) => column.max(keys_width),
+        None => width.saturating_sub(name_width).max(keys_width),
+    };
+    if name_start + name_width <= width {
+        return NamePlacement::SameLine(name_start - keys_width);
+    }
+    let indent = width.saturating_sub(name_width);
+    NamePlacement::Below(key_column.map_or(indent, |column| column.min(indent)))
+}
+
+// Truncates or pads `spans` to exactly `width` cells. A wide char that would
+// cross the edge is dropped and its cells padded instead.
+pub fn fit_spans(spans: &[Span<'static>], width: usize) -> Vec<Span<'static>> {
+    let mut fitted = Vec::with_capacity(spans.len() + 1);
+    let mut used = 0;
+    for span in spans {
+        let span_width = span.content.width();
+        if used + span_width <= width {
+            used += span_width;
+            fitted.push(span.clone());
+            continue;
+        }
+        let mut text = String::new();
+        for ch in span.content.chars() {
+            let ch_width = ch.width().unwrap_or(0);
+            if used + ch_width > width {
+                break;
+            }
+            used += ch_width;
+            text.push(ch);
+        }
+        fitted.push(Span::styled(text, span.style));
+        break;
+    }
+    if used < width {
+        fitted.push(Span::raw(" ".repeat(width - used)));
+    }
+    fitted
+}
@@ -1,365 +1,3209 @@
+mod messages;
+
+use arboard::Clipboard;
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent,
+        MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use messages::Messages;
+use nebula_keybind_menu::{
+    centered_rect, closest_name, color_warnings, dash_padding, default_keybinds, default_margin,
+    default_scroll_step, filter_keybinds, find_duplicates, fit_spans, normalize_keys, place_name,
+    split_query, trace_config, xdg_config_path, xdg_state_path, Action, ColorDepth, Config,
+    ConfigSource, Controls, KeyList, Keybind, Match, Mode, NameAlign, NamePlacement, PathStatus,
+    SearchMode, SearchOptions, SearchStyle, SortMode, Theme, TracedPath, SEQUENCE_SEPARATOR,
+};
 use ratatui::{
+    backend::TestBackend,
     prelude::*,
-    widgets::{Block, Borders, Paragraph},
+    widgets::{
+        Block, Borders, Clear, Padding, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState,
+        Wrap,
+    },
 };
-use serde::Deserialize;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
 use std::{
+    collections::{BTreeMap, HashMap},
     error::Error,
-    io::{self, Stdout},
-    path::PathBuf,
-    time::Instant,
+    io::{self, IsTerminal, Stdout, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
-use tui_input::{backend::crossterm::EventHandler, Input};
+use tui_input::{backend::crossterm::EventHandler, Input, InputRequest};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+type Tui = Terminal<CrosstermBackend<Screen>>;
+
+// Where the TUI is drawn: stdout, or the controlling terminal when stdout
+// is captured, as in `name=$(nebula-keybind-menu --print-selection)`
+enum Screen {
+    Stdout(Stdout),
+    Tty(std::fs::File),
+}
+
+impl Screen {
+    fn open() -> Self {
+        if io::stdout().is_terminal() {
+            return Screen::Stdout(io::stdout());
+        }
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open("/dev/tty")
+            .map_or_else(|_| Screen::Stdout(io::stdout()), Screen::Tty)
+    }
+}
+
+impl Write for Screen {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Screen::Stdout(out) => out.write(buf),
+            Screen::Tty(out) => out.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Screen::Stdout(out) => out.flush(),
+            Screen::Tty(out) => out.flush(),
+        }
+    }
+}
+
+// The system clipboard, connected on first use. It may be missing entirely
+// (headless sessions, SSH), in which case copying reports an error.
+#[derive(Default)]
+struct SystemClipboard {
+    inner: Option<Clipboard>,
+}
+
+impl SystemClipboard {
+    fn copy(&mut self, text: String, messages: &Messages) -> Result<(), String> {
+        if cfg!(all(
+            unix,
+            not(any(
+                target_os = "macos",
+                target_os = "android",
+                target_os = "emscripten"
+            ))
+        )) {
+            return copy_through_helper(&text, messages)
+                .map_err(|err| format!("{}: {}", messages.clipboard_unavailable, err));
+        }
+        let clipboard = match &mut self.inner {
+            Some(clipboard) => clipboard,
+            None => self.inner.insert(
+                Clipboard::new()
+                    .map_err(|err| format!("{}: {}", messages.clipboard_unavailable, err))?,
+            ),
+        };
+        clipboard
+            .set_text(text)
+            .map_err(|err| format!("{}: {}", messages.copy_failed, err))
+    }
+}
+
+// Where the main loop gets its input: the terminal, or a script in tests
+trait EventSource {
+    // Whether an event is ready within `timeout`
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool>;
+    // The next event, waiting for one if need be
+    fn read(&mut self) -> io::Result<Event>;
+}
+
+// Key, mouse and resize events from crossterm
+struct TerminalEvents;
+
+impl EventSource for TerminalEvents {
+    fn poll(&mut self, timeout: Duration) -> io::Result<bool> {
+        event::poll(timeout)
+    }
+
+    fn read(&mut self) -> io::Result<Event> {
+        event::read()
+    }
+}
+
+// First argument that runs the process as the clipboard helper instead
+const CLIPBOARD_HELPER_ARG: &str = "--clipboard-helper";
+
+// X11 and Wayland clipboards are served by the program that copied, so text
+// copied by the menu itself would be gone once it exits. Instead, a copy of
+// the menu is left in the background to hold `text` until something else is
+// copied. It answers with "ok" once it has set the text, or with the error.
+fn copy_through_helper(text: &str, messages: &Messages) -> Result<(), String> {
+    let mut helper =
+        std::process::Command::new(std::env::current_exe().map_err(|err| err.to_string())?);
+    helper
+        .arg(CLIPBOARD_HELPER_ARG)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null());
+    // Out of the terminal's process group, so Ctrl+C there doesn't reach it
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut helper, 0);
+    let mut child = helper.spawn().map_err(|err| err.to_string())?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(text.as_bytes())
+            .map_err(|err| err.to_string())?;
+    }
+    let mut reply = String::new();
+    if let Some(stdout) = child.stdout.take() {
+        io::BufRead::read_line(&mut io::BufReader::new(stdout), &mut reply)
+            .map_err(|err| err.to_string())?;
+    }
+    match reply.trim_end() {
+        "ok" => Ok(()),
+        "" => Err(messages.clipboard_helper_exited.to_string()),
+        err => Err(err.to_string()),
+    }
+}
+
+// The helper's side of `copy_through_helper`: sets the text read from stdin,
+// reports how that went, then serves it until another program replaces it
+#[cfg(all(
+    unix,
+    not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+))]
+fn serve_clipboard() -> ! {
+    use arboard::SetExtLinux;
+    let mut text = String::new();
+    if let Err(err) = io::Read::read_to_string(&mut io::stdin(), &mut text) {
+        println!("{}", err);
+        std::process::exit(1);
+    }
+    let mut clipboard = match Clipboard::new().and_then(|mut clipboard| {
+        clipboard.set_text(text.clone())?;
+        Ok(clipboard)
+    }) {
+        Ok(clipboard) => clipboard,
+        Err(err) => {
+            println!("{}", err);
+            std::process::exit(1);
+        }
+    };
+    println!("ok");
+    // Setting it again with `wait` blocks, holding the clipboard until
+    // another program takes it over.
+    let served = clipboard.set().wait().text(text);
+    std::process::exit(if served.is_ok() { 0 } else { 1 })
+}
+
+// Runs `command` through the shell with `text` on its stdin. The command
+// must exit by itself; tools like wl-copy fork to keep serving the text.
+fn run_copy_command(command: &str, text: &str) -> Result<(), String> {
+    let failed = |err: io::Error| format!("{} failed: {}", command, err);
+    let mut child = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .map_err(failed)?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes()).map_err(failed)?;
+    }
+    let status = child.wait().map_err(failed)?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{} failed: {}", command, status))
+    }
+}
+
+// Output format for --list
+#[derive(Clone, Copy, Default)]
+enum ListFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+// Command-line options
+#[derive(Default)]
+struct Args {
+    config: Option<PathBuf>,
+    list: bool,
+    format: ListFormat,
+    no_state: bool,
+    check: bool,
+    print_path: bool,
+    no_color: bool,
+    theme: Option<Theme>,
+    color_depth: Option<ColorDepth>,
+    stdin: bool,
+    bench: Option<usize>,
+    launcher: bool,
+    print_selection: bool,
+    show_disabled: bool,
+    init: bool,
+    force: bool,
+    help: bool,
+    version: bool,
+}
+
+const USAGE: &str = "\
+Usage: nebula-keybind-menu [OPTIONS]
+
+A searchable menu of your keybinds.
+
+Options:
+  --config <PATH>         Load this config file instead of the default locations
+  --list                  Print the keybinds and exit (the default when stdout
+                          isn't a terminal)
+  --format <text|json>    Output format for --list (default: text)
+  --check                 Validate the config and exit non-zero on problems
+  --print-path, --which   Show which config files would be loaded, and why
+  --no-state              Don't restore the last position and query
+  --no-color              Use the terminal's default colors
+  --theme <NAME>          Use a built-in theme: dark, light, solarized or nord
+  --color-depth <DEPTH>   Colors the terminal supports: 16, 256 or truecolor
+                          (default: detected from COLORTERM and TERM)
+  --stdin                 Read the config (TOML, or JSON) from stdin
+  --bench <N>             Render N frames off-screen and print the timings
+  --launcher              Run the selected keybind's command on Enter
+  --print-selection       Print the name of the keybind copied or launched
+                          last to stdout on exit
+  --show-disabled         Include keybinds with enabled = false, dimmed
+  --init                  Write a sample config.toml (to --config if given)
+  --force                 Let --init overwrite an existing config
+  -h, --help              Print this help and exit
+  -V, --version           Print the version and exit
+
+Exit status:
+  0    Closed without copying or launching a keybind
+  1    An error, such as a config that failed --check
+  2    Invalid command line
+  10   A keybind was copied or launched
+
+Environment:
+  NEBULA_KEYBIND_MENU_PROFILE    Print startup, load and frame timings to
+                                 stderr on exit when set
+  NEBULA_KEYBIND_MENU_CONFIG     Config file to try before the default locations
+  NO_COLOR                       Same as --no-color when set and non-empty
+  COLORTERM, TERM                Used to detect the color depth
+  XDG_CONFIG_HOME                Base directory for the user config
+  XDG_CONFIG_DIRS                Base directories for the system config
+  XDG_STATE_HOME                 Base directory for the saved state
+  XDG_CACHE_HOME                 Base directory for the parsed-config cache
+";
+
+// Session state remembered between launches
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+struct State {
+    scroll_offset: u16,
+    selected_index: usize,
+    query: String,
+    // Past queries, most recent first
+    history: Vec<String>,
+    // Keyed by keybind name
+    usage: BTreeMap<String, Usage>,
+}
+
+// How many past queries are remembered
+const HISTORY_LIMIT: usize = 50;
+
+// How much a keybind has been used, with older uses counting for less
+#[derive(Clone, Copy, Default, Deserialize, Serialize)]
+struct Usage {
+    weight: f64,
+    // Seconds since the Unix epoch
+    last_used: u64,
+}
+
+// Days after which a use counts half as much
+const USAGE_HALF_LIFE_DAYS: f64 = 14.0;
+
+impl Usage {
+    // The weight decayed from the last use to `now`
+    fn weight_at(&self, now: u64) -> f64 {
+        let days = now.saturating_sub(self.last_used) as f64 / 86_400.0;
+        self.weight * 0.5f64.powf(days / USAGE_HALF_LIFE_DAYS)
+    }
 
-type Tui = Terminal<CrosstermBackend<Stdout>>;
+    fn record(&mut self, now: u64) {
+        self.weight = self.weight_at(now) + 1.0;
+        self.last_used = now;
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+impl State {
+    fn path() -> Option<PathBuf> {
+        xdg_state_path().map(|path| path.join("nebula-keybind-menu").join("state.toml"))
+    }
+
+    // Missing or unreadable state just means a fresh start
+    fn load() -> Self {
+        Self::path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
 
-#[derive(Clone, Deserialize)]
-struct Keybind {
-    keys: String,
-    name: String,
-    desc: String,
+    fn save(&self) -> io::Result<()> {
+        let Some(path) = Self::path() else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let contents = toml::to_string(self).map_err(io::Error::other)?;
+        std::fs::write(path, contents)
+    }
+}
+
+// Timings for NEBULA_KEYBIND_MENU_PROFILE. They're printed after the
+// terminal is restored, since stderr would land on the alternate screen.
+#[derive(Default)]
+struct Profile {
+    lines: Vec<String>,
+    frames: u32,
+    // Frames that had to recompute the matches first
+    filtered: u32,
+    filter: Duration,
+    render: Duration,
+    slowest: Duration,
 }
 
-#[derive(Deserialize)]
-struct Config {
-    keybinds: Vec<Keybind>,
+impl Profile {
+    fn log(&mut self, line: String) {
+        self.lines.push(line);
+    }
+
+    fn record_frame(&mut self, filtered: bool, filter: Duration, render: Duration) {
+        self.frames += 1;
+        self.filtered += u32::from(filtered);
+        self.filter += filter;
+        self.render += render;
+        self.slowest = self.slowest.max(filter + render);
+    }
+
+    fn report(&self) {
+        for line in &self.lines {
+            eprintln!("{}", line);
+        }
+        if self.frames > 0 {
+            eprintln!(
+                "frames: {} ({} filtered), {:.2?} filter and {:.2?} render on average, slowest {:.2?}",
+                self.frames,
+                self.filtered,
+                self.filter / self.frames,
+                self.render / self.frames,
+                self.slowest
+            );
+        }
+    }
 }
 
 struct App {
     should_quit: bool,
+    mode: Mode,
+    // Whether the details popup for the selected keybind is open
+    show_details: bool,
+    // Index of categories to jump to, open over the list
+    show_categories: bool,
+    source: ConfigSource,
+    persist_state: bool,
+    // Last session's query, applied once the config says whether to use it
+    restored_query: Option<String>,
+    // False for --no-color or NO_COLOR; the theme is ignored
+    color: bool,
+    // Theme from --theme, used instead of the config's
+    theme_override: Option<Theme>,
+    // Theme colors are reduced to this before drawing
+    color_depth: ColorDepth,
+    // Enter runs the selected keybind's command, if it has one
+    launcher: bool,
+    // Command to run once the terminal is restored
+    launch: Option<String>,
+    // Name of the last keybind copied or launched, for the exit code
+    accepted: Option<String>,
+    // Keep `enabled = false` entries, drawn dimmed
+    show_disabled: bool,
     search_input: Input,
+    // Past queries, most recent first; recorded on copy and on exit
+    history: Vec<String>,
+    // Position while stepping through `history`, and the query typed before
+    history_index: Option<usize>,
+    history_draft: String,
+    // Loaded from and saved to the state file, but only updated when
+    // `track_usage` is set
+    usage: BTreeMap<String, Usage>,
+    track_usage: bool,
+    fold_diacritics: bool,
     items: Vec<Keybind>,
-    placeholder_text: &'static str,
+    search_mode: SearchMode,
+    // Mode to return to when regex search is toggled off
+    plain_search_mode: SearchMode,
+    // Last pattern that compiled, and whether it's the current query
+    regex: Option<Regex>,
+    regex_valid: bool,
+    theme: Theme,
+    controls: Controls,
+    sort_mode: SortMode,
+    // Flips whichever order `sort_mode` gives, and stays as the mode cycles
+    sort_reverse: bool,
+    show_footer: bool,
+    case_sensitive: bool,
+    // Grid columns for the list; 0 picks as many as fit
+    columns: usize,
+    name_align: NameAlign,
+    key_width: usize,
+    search_style: SearchStyle,
+    margin: u16,
+    spacing: bool,
+    show_descriptions: bool,
+    // Overrides `spacing` and `show_descriptions` while set
+    compact: bool,
+    wrap: bool,
+    scroll_step: usize,
+    page_overlap: u16,
+    auto_select_single: bool,
+    auto_accept_single: bool,
+    placeholder_text: String,
+    title_text: String,
+    show_title: bool,
+    // UI strings for the configured or environment language
+    messages: &'static Messages,
+    // Set when NEBULA_KEYBIND_MENU_PROFILE is
+    profile: Option<Profile>,
+    // Whether anything on screen may have changed since the last draw
+    needs_redraw: bool,
+    // Set by the edit key; `run` opens the editor, since it owns the terminal
+    edit_requested: bool,
     first_frame_logged: bool,
     items_loaded: bool,
     scroll_offset: u16,
     content_height: u16,
+    // Screen area and scroll limit of the list, from the last frame
+    content_area: Rect,
+    max_scroll: u16,
+    selected_index: usize,
+    // Set when the selection moves, so the next frame scrolls it into view
+    follow_selection: bool,
+    // Where each rendered item was placed, from the last frame
+    item_lines: Vec<ItemPlace>,
+    // Line of each category heading and the match it's above, from the
+    // last frame
+    category_lines: Vec<(usize, usize)>,
+    clipboard: SystemClipboard,
+    // Replaces the clipboard with an external command, e.g. "wl-copy"
+    copy_command: Option<String>,
+    // Result of a copy command still running in the background, and the
+    // name of the keybind being copied
+    copy_result: Option<(mpsc::Receiver<Result<(), String>>, String)>,
+    // Shown until the next keypress
+    error_message: Option<String>,
+    // A short notice and when it was set, cleared after STATUS_DURATION
+    status: Option<(String, Instant)>,
+    // Why the preferred config couldn't be used, shown until a good reload
+    config_error: Option<String>,
+    // Number of key combos bound more than once in the loaded config
+    duplicate_count: usize,
+    // For each item, the other items bound to the same keys
+    conflicts: Vec<Vec<usize>>,
+    // Filtered items for the current query, refreshed when marked dirty
+    matches: Vec<Match>,
+    // A keybind name the query may have been a typo of, when nothing matches
+    suggestion: Option<String>,
+    matches_dirty: bool,
 }
 
 impl App {
-    fn new() -> Self {
-        Self {
+    fn new(
+        source: ConfigSource,
+        persist_state: bool,
+        color: bool,
+        theme_override: Option<Theme>,
+        color_depth: ColorDepth,
+        launcher: bool,
+    ) -> Self {
+        let mut app = Self {
             should_quit: false,
+            mode: Mode::Insert,
+            show_details: false,
+            show_categories: false,
+            source,
+            persist_state,
+            restored_query: None,
+            color,
+            theme_override,
+            color_depth,
+            launcher,
+            launch: None,
+            accepted: None,
+            show_disabled: false,
             search_input: Input::default(),
+            history: Vec::new(),
+            usage: BTreeMap::new(),
+            track_usage: false,
+            fold_diacritics: false,
+            history_index: None,
+            history_draft: String::new(),
             items: Vec::new(),
-            placeholder_text: "Type to search keybinds",
+            search_mode: SearchMode::default(),
+            plain_search_mode: SearchMode::default(),
+            regex: None,
+            regex_valid: true,
+            theme: if color {
+                theme_override.unwrap_or_default().with_depth(color_depth)
+            } else {
+                Theme::monochrome()
+            },
+            controls: Controls::default(),
+            sort_mode: SortMode::default(),
+            sort_reverse: false,
+            show_footer: false,
+            case_sensitive: false,
+            columns: 0,
+            name_align: NameAlign::default(),
+            search_style: SearchStyle::default(),
+            key_width: 0,
+            margin: default_margin(),
+            spacing: true,
+            show_descriptions: true,
+            compact: false,
+            wrap: false,
+            scroll_step: default_scroll_step(),
+            page_overlap: 0,
+            auto_select_single: false,
+            auto_accept_single: false,
+            placeholder_text: String::new(),
+            title_text: String::new(),
+            show_title: true,
+            messages: messages::for_lang(&messages::env_lang()),
+            profile: None,
+            needs_redraw: true,
+            edit_requested: false,
             first_frame_logged: false,
             items_loaded: false,
             scroll_offset: 0,
             content_height: 0,
+            content_area: Rect::default(),
+            max_scroll: 0,
+            selected_index: 0,
+            follow_selection: true,
+            item_lines: Vec::new(),
+            category_lines: Vec::new(),
+            clipboard: SystemClipboard::default(),
+            copy_command: None,
+            copy_result: None,
+            error_message: None,
+            status: None,
+            config_error: None,
+            duplicate_count: 0,
+            conflicts: Vec::new(),
+            matches: Vec::new(),
+            suggestion: None,
+            matches_dirty: true,
+        };
+        if persist_state {
+            // Rendering clamps these if the list has shrunk since last time.
+            let state = State::load();
+            app.scroll_offset = state.scroll_offset;
+            app.selected_index = state.selected_index;
+            app.follow_selection = false;
+            app.restored_query = Some(state.query).filter(|query| !query.is_empty());
+            app.history = state.history;
+            app.usage = state.usage;
+        }
+        app
+    }
+
+    // Remembers the scroll position, query and history for the next launch
+    fn save_state(&self) {
+        if !self.persist_state {
+            return;
         }
+        let mut history = self.history.clone();
+        remember_query(&mut history, self.search_input.value());
+        let state = State {
+            scroll_offset: self.scroll_offset,
+            selected_index: self.selected_index,
+            query: self.search_input.value().to_string(),
+            history,
+            usage: self.usage.clone(),
+        };
+        // Best effort; failing to save shouldn't turn a clean exit into an error.
+        let _ = state.save();
     }
 
     // Main application loop
-    fn run(&mut self, terminal: &mut Tui, profiling: bool, start: Instant) -> io::Result<()> {
+    fn run<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        events: &mut impl EventSource,
+        start: Instant,
+    ) -> io::Result<()> {
+        // Load on a background thread so the UI is live while it reads. If
+        // the user quits first, the result is dropped along with `loaded`.
+        let (sender, loaded) = mpsc::channel();
+        let source = self.source.clone();
+        std::thread::spawn(move || {
+            let load_start = Instant::now();
+            let result = source.load();
+            let _ = sender.send((result, load_start.elapsed()));
+        });
+
         while !self.should_quit {
-            self.draw(terminal);
-            if profiling && !self.first_frame_logged {
-                eprintln!("startup: first frame in {:.2?}", start.elapsed());
+            if self.needs_redraw {
+                self.draw(terminal);
+                self.needs_redraw = false;
+            }
+            if let Some(profile) = self.profile.as_mut().filter(|_| !self.first_frame_logged) {
+                profile.log(format!("startup: first frame in {:.2?}", start.elapsed()));
                 self.first_frame_logged = true;
             }
-            if !self.items_loaded {
-                self.items = load_keybinds();
-                self.items_loaded = true;
+            // Check back regularly until the config or copy result arrives,
+            // or the status message is due to clear; otherwise just wait.
+            let waiting = !self.items_loaded || self.copy_result.is_some() || self.status.is_some();
+            self.handle_events(events, waiting.then_some(Duration::from_millis(50)))?;
+            if self.edit_requested {
+                self.edit_config(terminal)?;
+            }
+            if let Ok(((config, error), load_time)) = loaded.try_recv() {
+                if let Some(profile) = &mut self.profile {
+                    profile.log(format!("load: config read in {:.2?}", load_time));
+                }
+                self.finish_loading(config, error);
+            }
+            self.check_copy_result();
+            if self
+                .status
+                .as_ref()
+                .is_some_and(|(_, since)| since.elapsed() >= STATUS_DURATION)
+            {
+                self.status = None;
+                self.needs_redraw = true;
             }
-            self.handle_events()?;
         }
         Ok(())
     }
 
-    // Handles input events
-    fn handle_events(&mut self) -> io::Result<()> {
-        if let Event::Key(key) = event::read()? {
-            if key.kind == KeyEventKind::Press {
-                match key.code {
-                    KeyCode::Esc => self.should_quit = true,
-                    KeyCode::Up => self.scroll_offset = self.scroll_offset.saturating_sub(1),
-                    KeyCode::Down => self.scroll_offset = self.scroll_offset.saturating_add(1),
-                    KeyCode::PageUp => {
-                        self.scroll_offset = self
-                            .scroll_offset
-                            .saturating_sub(self.content_height.max(1));
-                    }
-                    KeyCode::PageDown => {
-                        self.scroll_offset = self
-                            .scroll_offset
-                            .saturating_add(self.content_height.max(1));
-                    }
-                    KeyCode::Char('c') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
-                        self.should_quit = true
-                    }
-                    _ => {
-                        self.search_input.handle_event(&Event::Key(key));
-                        self.scroll_offset = 0;
-                    }
-                }
+    // Leaves the TUI to edit the config in $VISUAL or $EDITOR (vi if neither
+    // is set), then comes back and reloads it
+    fn edit_config<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> io::Result<()> {
+        self.edit_requested = false;
+        self.needs_redraw = true;
+        let Some(path) = self.source.editable_path() else {
+            self.error_message = Some(self.messages.nothing_to_edit.to_string());
+            return Ok(());
+        };
+        let editor = ["VISUAL", "EDITOR"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.trim().is_empty())
+            .unwrap_or_else(|| "vi".to_string());
+
+        restore_terminal(&mut Screen::open())?;
+        EDITOR_RUNNING.store(true, Ordering::SeqCst);
+        // Through the shell, so an editor with arguments like "code -w" works.
+        // It gets the screen even when our stdout is captured.
+        let stdout = match Screen::open() {
+            Screen::Tty(tty) => std::process::Stdio::from(tty),
+            Screen::Stdout(_) => std::process::Stdio::inherit(),
+        };
+        let status = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(format!("{} \"$1\"", editor))
+            .arg("sh")
+            .arg(&path)
+            .stdout(stdout)
+            .status();
+        EDITOR_RUNNING.store(false, Ordering::SeqCst);
+        setup_terminal(&mut Screen::open())?;
+        terminal.clear()?;
+
+        match status {
+            Ok(status) if status.success() => self.reload_config(),
+            Ok(status) => {
+                self.error_message = Some(format!(
+                    "{} {} {}",
+                    editor, self.messages.editor_exited, status
+                ))
+            }
+            Err(err) => {
+                self.error_message = Some(format!(
+                    "{} {}: {}",
+                    self.messages.editor_failed, editor, err
+                ))
             }
         }
         Ok(())
     }
 
-    fn draw(&mut self, terminal: &mut Tui) {
-        terminal.draw(|frame| self.render_ui(frame)).unwrap();
+    // Shows `message` in the spacer row for a couple of seconds
+    fn set_status(&mut self, message: &str) {
+        self.status = Some((message.to_string(), Instant::now()));
+        self.needs_redraw = true;
     }
 
-    // Renders the entire UI
-    fn render_ui(&mut self, frame: &mut Frame) {
-        let main_layout = Layout::default()
-            .direction(Direction::Vertical)
-            .margin(1)
-            .constraints(vec![
-                Constraint::Length(1), // Title
-                Constraint::Length(4), // Search
-                Constraint::Length(1), // Spacer
-                Constraint::Min(0),    // Content
-            ])
-            .split(frame.size());
-
-        self.render_title(frame, main_layout[0]);
-        self.render_search(frame, main_layout[1]);
-        self.render_content(frame, main_layout[3]);
-        // Footer removed intentionally.
+    // Loads the config on the current thread
+    fn load_items(&mut self) {
+        let (config, error) = self.source.load();
+        self.finish_loading(config, error);
     }
 
-    // Renders the title bar
-    fn render_title(&self, frame: &mut Frame, area: Rect) {
-        let esc_text = "Esc to close";
-        let esc_width = esc_text.len() as u16;
-        let chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Min(0), Constraint::Length(esc_width)])
-            .split(area);
-
-        let title = Paragraph::new(Span::styled(
-            "  Keybinds",
-            Style::new().fg(Color::Green).add_modifier(Modifier::BOLD),
-        ))
-        .alignment(Alignment::Left);
-        frame.render_widget(title, chunks[0]);
-
-        let esc_hint = Paragraph::new(esc_text)
-            .style(Style::new().fg(Color::Black))
-            .alignment(Alignment::Right);
-        frame.render_widget(esc_hint, chunks[1]);
+    fn finish_loading(&mut self, config: Config, error: Option<String>) {
+        self.apply_config(config);
+        self.config_error = error;
+        self.items_loaded = true;
+        self.needs_redraw = true;
     }
 
-    // Renders the search input box
-    fn render_search(&self, frame: &mut Frame, area: Rect) {
-        let input_area = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Length(1), Constraint::Length(3)])
-            .split(area);
+    fn apply_config(&mut self, mut config: Config) {
+        // Anything typed while the config was loading wins.
+        if let Some(query) = self.restored_query.take() {
+            if config.restore_query && self.search_input.value().is_empty() {
+                self.search_input = Input::new(query);
+            }
+        }
+        if !self.show_disabled {
+            config.drop_disabled();
+        }
+        self.duplicate_count = find_duplicates(&config.keybinds).len();
+        self.conflicts = find_conflicts(&config.keybinds);
+        if let Some(warning) = color_warnings(&config.keybinds).into_iter().next() {
+            self.error_message = Some(warning);
+        }
+        self.items = config.keybinds;
+        self.search_mode = config.search;
+        if config.search != SearchMode::Regex {
+            self.plain_search_mode = config.search;
+        }
+        if self.color {
+            self.theme = self
+                .theme_override
+                .unwrap_or(config.theme)
+                .with_depth(self.color_depth);
+        }
+        // Text styles aren't colors, so they apply with --theme or without
+        // colors too.
+        self.theme.key_style = config.theme.key_style;
+        self.theme.name_style = config.theme.name_style;
+        self.controls = config.controls;
+        self.sort_mode = config.sort;
+        self.sort_reverse = config.sort_reverse;
+        self.show_footer = config.footer;
+        self.case_sensitive = config.case_sensitive;
+        self.columns = config.columns;
+        self.name_align = config.name_align;
+        self.search_style = config.layout.search_style;
+        self.key_width = config.key_width;
+        self.margin = config.margin;
+        self.spacing = config.spacing;
+        self.show_descriptions = config.descriptions;
+        self.compact = config.compact;
+        self.wrap = config.wrap;
+        self.track_usage = config.track_usage;
+        self.fold_diacritics = config.fold_diacritics;
+        self.scroll_step = config.scroll_step.max(1);
+        self.page_overlap = config.page_overlap;
+        self.auto_select_single = config.auto_select_single;
+        self.auto_accept_single = config.auto_accept_single;
+        self.copy_command = config.copy_command;
+        self.messages = match &config.lang {
+            Some(lang) => messages::for_lang(lang),
+            None => messages::for_lang(&messages::env_lang()),
+        };
+        if config.no_keybinds {
+            self.error_message = Some(self.messages.no_keybinds.to_string());
+        }
+        self.placeholder_text = config
+            .placeholder
+            .unwrap_or_else(|| self.messages.placeholder.to_string());
+        self.title_text = config
+            .title
+            .unwrap_or_else(|| self.messages.title.to_string());
+        self.show_title = config.title_bar;
+        self.update_regex();
+        self.matches_dirty = true;
+    }
 
-        let (input_text, input_style) = if self.search_input.value().is_empty() {
-            (
-                self.placeholder_text.to_string(),
-                Style::new().fg(Color::Black),
-            )
+    // Switches between regex search and the plain search mode
+    fn toggle_regex(&mut self) {
+        self.search_mode = if self.search_mode == SearchMode::Regex {
+            self.plain_search_mode
         } else {
-            (
-                self.search_input.value().to_string(),
-                Style::new().fg(Color::White),
-            )
+            SearchMode::Regex
         };
-
-        let input_line = Line::from(Span::styled(format!(" {} ", input_text), input_style));
-        let input_paragraph = Paragraph::new(Text::from(input_line)).block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::new().fg(Color::Black)),
-        );
-        frame.render_widget(input_paragraph, input_area[1]);
+        self.update_regex();
+        self.matches_dirty = true;
+        self.scroll_offset = 0;
+        self.selected_index = 0;
     }
 
-    // Renders the filtered list of keybinds
-    fn render_content(&mut self, frame: &mut Frame, area: Rect) {
-        if !self.items_loaded {
-            let message =
-                Paragraph::new("Loading keybinds...").style(Style::new().fg(Color::White));
-            frame.render_widget(message, area);
+    // Recompiles the query as a regex, keeping the last valid pattern if
+    // the query is a partial or broken expression.
+    fn update_regex(&mut self) {
+        if self.search_mode != SearchMode::Regex {
             return;
         }
+        let query = split_query(self.search_input.value());
+        match RegexBuilder::new(&query.text)
+            .case_insensitive(!self.case_sensitive)
+            .build()
+        {
+            Ok(regex) => {
+                self.regex = Some(regex);
+                self.regex_valid = true;
+            }
+            Err(_) => self.regex_valid = false,
+        }
+    }
 
-        self.content_height = area.height;
-        let query = self.search_input.value().to_lowercase();
-        let filtered_items: Vec<&Keybind> = self
-            .items
-            .iter()
-            .filter(|item| {
-                item.name.to_lowercase().contains(&query)
-                    || item.desc.to_lowercase().contains(&query)
-            })
-            .collect();
-
-        if filtered_items.is_empty() {
-            let message = Paragraph::new("No matches. Try a different query.")
-                .style(Style::new().fg(Color::White));
-            frame.render_widget(message, area);
+    // Reloads the config from disk, keeping the old items if it's broken
+    fn reload_config(&mut self) {
+        if matches!(self.source, ConfigSource::Stdin(_)) {
+            self.error_message = Some(self.messages.nothing_to_reload.to_string());
             return;
         }
+        let (config, error) = self.source.load();
+        if let Some(error) = error {
+            self.error_message = Some(format!("{}: {}", self.messages.reload_failed, error));
+            return;
+        }
+        self.apply_config(config);
+        self.config_error = None;
+        self.set_status(self.messages.reloaded);
+        self.scroll_offset = 0;
+        self.selected_index = 0;
+        self.refresh_matches();
+        if self.matches.is_empty() {
+            self.search_input.reset();
+            self.matches_dirty = true;
+        }
+    }
 
-        let mut lines: Vec<Line<'static>> = Vec::new();
-        let inner_width = area.width;
-        for item in filtered_items {
-            let key_text = format!("{} ", item.keys);
-            let key_span = Span::styled(key_text.clone(), Style::new().fg(Color::White).bold());
-            let name_text = item.name.clone();
-            let reserved = key_text.len() + name_text.len();
-            let spacer_len = if inner_width as usize > reserved {
-                inner_width as usize - reserved
-            } else {
-                1
-            };
-            let name_span = Span::styled(name_text, Style::new().bold());
-            lines.push(Line::from(vec![
-                key_span,
-                Span::raw(" ".repeat(spacer_len)),
-                name_span,
-            ]));
-            if !item.desc.is_empty() {
-                lines.push(Self::make_desc_line(&item.desc, inner_width));
+    // Waits for the next terminal event, up to `timeout` if given, then
+    // handles it along with any others already queued. Draining the queue
+    // before the next draw keeps held keys from lagging behind.
+    fn handle_events(
+        &mut self,
+        events: &mut impl EventSource,
+        timeout: Option<Duration>,
+    ) -> io::Result<()> {
+        if let Some(timeout) = timeout {
+            if !events.poll(timeout)? {
+                return Ok(());
             }
-            lines.push(Line::from(" "));
         }
+        self.handle_event(events.read()?);
+        while !self.should_quit && events.poll(Duration::ZERO)? {
+            self.handle_event(events.read()?);
+        }
+        Ok(())
+    }
+
+    // Applies one input event; kept apart from reading so events can be fed
+    // in without a terminal
+    fn handle_event(&mut self, event: Event) {
+        match event {
+            Event::Key(key) if key.kind == KeyEventKind::Press => self.handle_key(key),
+            // Plain pointer movement changes nothing on screen.
+            Event::Mouse(mouse) if mouse.kind == MouseEventKind::Moved => return,
+            Event::Mouse(mouse) => self.handle_mouse(mouse),
+            // The next draw picks up the new size and re-clamps the scroll;
+            // keep the selection visible in the resized viewport.
+            Event::Resize(_, _) => self.follow_selection = true,
+            Event::Paste(text) => self.paste(&text),
+            _ => return,
+        }
+        self.needs_redraw = true;
+    }
 
-        if lines.is_empty() {
-            let message = Paragraph::new("No matches. Try a different query.")
-                .style(Style::new().fg(Color::White));
-            frame.render_widget(message, area);
+    // Inserts pasted text into the search in one go. Line breaks and tabs
+    // become spaces and other control characters are dropped.
+    fn paste(&mut self, text: &str) {
+        if self.show_details || self.show_categories {
             return;
         }
-
-        let max_scroll = lines.len().saturating_sub(area.height as usize);
-        let scroll = self.scroll_offset.min(max_scroll as u16);
-        let list = Paragraph::new(Text::from(lines))
-            .scroll((scroll, 0))
-            .style(Style::new().fg(Color::White));
-        frame.render_widget(list, area);
+        self.mode = Mode::Insert;
+        let mut changed = false;
+        for ch in text.chars() {
+            let ch = match ch {
+                '\n' | '\r' | '\t' => ' ',
+                ch if ch.is_control() => continue,
+                ch => ch,
+            };
+            changed |= self
+                .search_input
+                .handle(InputRequest::InsertChar(ch))
+                .is_some_and(|change| change.value);
+        }
+        if changed {
+            self.query_edited();
+        }
     }
 
-    // Creates a description line with dashes on either side
-    fn make_desc_line(desc: &str, width: u16) -> Line<'static> {
-        let desc_style = Style::new().fg(Color::Black);
-        let inner_width = width as usize;
-        let trimmed = desc.trim();
+    // Refilters from the top after the query was typed into
+    fn query_edited(&mut self) {
+        let previous = self.matches.len();
+        self.history_index = None;
+        self.update_regex();
+        self.matches_dirty = true;
+        self.scroll_offset = 0;
+        self.selected_index = 0;
+        // Acts once, when the edit narrows the list to its last match.
+        if self.auto_select_single && self.auto_accept_single {
+            self.refresh_matches();
+            if self.matches.len() == 1 && previous != 1 {
+                self.activate_selected();
+            }
+        }
+    }
 
-        if inner_width == 0 {
-            return Line::from(Span::styled(trimmed.to_string(), desc_style));
+    fn handle_key(&mut self, key: KeyEvent) {
+        self.error_message = None;
+        if self.show_details {
+            self.handle_details_key(key);
+            return;
+        }
+        if self.show_categories {
+            self.handle_categories_key(key);
+            return;
+        }
+        if let Some(action) = self.controls.action_for(&key, self.mode) {
+            self.perform(action);
+            return;
         }
+        if self.mode == Mode::Normal && self.handle_normal_key(key) {
+            return;
+        }
+        let changed = match alt_edit_request(&key) {
+            Some(request) => self.search_input.handle(request),
+            None => self.search_input.handle_event(&Event::Key(key)),
+        };
+        // Moving the cursor leaves the list where it is.
+        if changed.is_some_and(|change| change.value) {
+            self.query_edited();
+        }
+    }
 
-        let desc_len = trimmed.len();
-        let min_needed = desc_len + 4;
-        if inner_width < min_needed {
-            return Line::from(Span::styled(trimmed.to_string(), desc_style));
+    // The details popup is modal: it closes on Esc, q, Enter or its own
+    // key, and only the quit key still works behind it.
+    fn handle_details_key(&mut self, key: KeyEvent) {
+        match self.controls.action_for(&key, Mode::Normal) {
+            Some(Action::Quit) => self.should_quit = true,
+            Some(Action::Details) => self.show_details = false,
+            _ => {
+                if matches!(key.code, KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q')) {
+                    self.show_details = false;
+                }
+            }
         }
+    }
 
-        let dash_total = inner_width - desc_len - 2;
-        let left = dash_total / 2;
-        let right = dash_total - left;
-        let line = format!("{} {} {}", "-".repeat(left), trimmed, "-".repeat(right));
-        Line::from(Span::styled(line, desc_style))
+    // The category index is modal too: a category's label jumps to it, and
+    // Esc, q or the index's own key closes it.
+    fn handle_categories_key(&mut self, key: KeyEvent) {
+        match self.controls.action_for(&key, Mode::Normal) {
+            Some(Action::Quit) => self.should_quit = true,
+            Some(Action::JumpToCategory) => self.show_categories = false,
+            _ => match key.code {
+                KeyCode::Char(label)
+                    if key.modifiers.difference(KeyModifiers::SHIFT).is_empty() =>
+                {
+                    if let Some(index) = CATEGORY_LABELS.find(label) {
+                        self.jump_to_category(index);
+                    }
+                }
+                KeyCode::Esc => self.show_categories = false,
+                _ => {}
+            },
+        }
     }
 
-    // Footer intentionally removed.
-}
+    // Replaces the query with an older or newer one from the history. Going
+    // past the newest brings back what was typed before.
+    fn step_history(&mut self, older: bool) {
+        let index = match (self.history_index, older) {
+            (None, true) => 0,
+            (None, false) => return,
+            (Some(index), true) => index + 1,
+            (Some(0), false) => {
+                self.history_index = None;
+                let draft = std::mem::take(&mut self.history_draft);
+                self.set_query(draft);
+                return;
+            }
+            (Some(index), false) => index - 1,
+        };
+        let Some(query) = self.history.get(index).cloned() else {
+            return;
+        };
+        if self.history_index.is_none() {
+            self.history_draft = self.search_input.value().to_string();
+        }
+        self.history_index = Some(index);
+        self.set_query(query);
+    }
 
-// Initializes terminal in alternate screen and raw mode
-fn init_terminal() -> io::Result<Tui> {
-    execute!(io::stdout(), EnterAlternateScreen)?;
-    enable_raw_mode()?;
-    Terminal::new(CrosstermBackend::new(io::stdout()))
-}
+    fn set_query(&mut self, query: String) {
+        self.search_input = Input::new(query);
+        self.update_regex();
+        self.matches_dirty = true;
+        self.scroll_offset = 0;
+        self.selected_index = 0;
+    }
 
-// Restores terminal to original state
-fn restore_terminal() -> io::Result<()> {
-    disable_raw_mode()?;
-    execute!(io::stdout(), LeaveAlternateScreen)?;
-    Ok(())
-}
+    // Scrolls the category's heading to the top and selects its first entry
+    fn jump_to_category(&mut self, index: usize) {
+        let Some(&(line, first)) = self.category_lines.get(index) else {
+            return;
+        };
+        self.show_categories = false;
+        self.selected_index = first;
+        self.scroll_offset = (line as u16).min(self.max_scroll);
+        self.follow_selection = false;
+    }
 
-// Loads keybinds from user or system config, or returns defaults
-fn load_keybinds() -> Vec<Keybind> {
-    let user_config = xdg_config_path().map(|mut path| {
-        path.push("nebula-keybind-menu");
-        path.push("config.toml");
-        path
-    });
-    let system_config = PathBuf::from("/usr/share/nebula-keybind-menu/config.toml");
-
-    // Try user config
-    if let Some(path) = user_config {
-        if let Ok(contents) = std::fs::read_to_string(&path) {
-            if let Ok(config) = toml::from_str::<Config>(&contents) {
-                if !config.keybinds.is_empty() {
-                    return config.keybinds;
+    fn perform(&mut self, action: Action) {
+        match action {
+            Action::Quit => self.should_quit = true,
+            // Pressed again in normal mode, the same key closes the menu.
+            Action::NormalMode => match self.mode {
+                Mode::Insert => self.mode = Mode::Normal,
+                Mode::Normal => self.should_quit = true,
+            },
+            Action::ScrollUp => self.select_previous(),
+            Action::ScrollDown => self.select_next(),
+            Action::PageUp => self.select_page_up(),
+            Action::PageDown => self.select_page_down(),
+            Action::Copy => self.activate_selected(),
+            Action::CopyLine => self.copy_selected_line(),
+            Action::Reload => self.reload_config(),
+            Action::ToggleRegex => self.toggle_regex(),
+            Action::ToggleCase => {
+                self.case_sensitive = !self.case_sensitive;
+                self.update_regex();
+                self.matches_dirty = true;
+                self.scroll_offset = 0;
+                self.selected_index = 0;
+            }
+            Action::NextMatch => self.cycle_selection(true),
+            Action::PreviousMatch => self.cycle_selection(false),
+            Action::Details => self.show_details = !self.matches.is_empty(),
+            Action::JumpToCategory => self.show_categories = !self.category_lines.is_empty(),
+            Action::ToggleCompact => {
+                // The next frame rebuilds the lines; keep the selection in view.
+                self.compact = !self.compact;
+                self.follow_selection = true;
+            }
+            Action::ClearSearch => {
+                self.history_index = None;
+                self.set_query(String::new());
+            }
+            Action::CycleSort => {
+                self.sort_mode = self.sort_mode.next();
+                if self.sort_mode == SortMode::Frequent && !self.track_usage {
+                    self.sort_mode = self.sort_mode.next();
                 }
+                self.matches_dirty = true;
+                self.scroll_offset = 0;
+                self.selected_index = 0;
+            }
+            Action::HistoryPrevious => self.step_history(true),
+            Action::HistoryNext => self.step_history(false),
+            Action::EditConfig => self.edit_requested = true,
+            Action::ReverseSort => {
+                self.sort_reverse = !self.sort_reverse;
+                self.matches_dirty = true;
+                self.scroll_offset = 0;
+                self.selected_index = 0;
             }
         }
     }
 
-    // Try system config
-    if let Ok(contents) = std::fs::read_to_string(&system_config) {
-        if let Ok(config) = toml::from_str::<Config>(&contents) {
-            if !config.keybinds.is_empty() {
-                return config.keybinds;
-            }
+    // Handles navigation letters in normal mode. Returns false for keys
+    // that behave the same in both modes.
+    fn handle_normal_key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('q') => self.should_quit = true,
+            KeyCode::Char('i') | KeyCode::Char('/') => self.mode = Mode::Insert,
+            KeyCode::Char('j') => self.select_next(),
+            KeyCode::Char('k') => self.select_previous(),
+            KeyCode::Char('d') if ctrl => self.select_page_down(),
+            KeyCode::Char('u') if ctrl => self.select_page_up(),
+            KeyCode::Char('g') => self.select_first(),
+            KeyCode::Char('G') => self.select_last(),
+            KeyCode::Char('y') => self.copy_selected_keys(),
+            KeyCode::Char('Y') => self.copy_selected_line(),
+            // Unbound letters are ignored rather than typed into the search.
+            KeyCode::Char(_) if !ctrl => {}
+            _ => return false,
         }
+        true
     }
 
-    // Fallback default keybinds
-    vec![
-        Keybind {
-            keys: "SUPER + SPACE".to_string(),
-            name: "Launcher".to_string(),
-            desc: "Open app launcher".to_string(),
-        },
-        Keybind {
-            keys: "SUPER + B".to_string(),
-            name: "Web Browser".to_string(),
-            desc: "Open default browser".to_string(),
-        },
-        Keybind {
-            keys: "SUPER + ENTER".to_string(),
-            name: "Terminal".to_string(),
-            desc: "Open terminal".to_string(),
-        },
-        Keybind {
-            keys: "SUPER + Q".to_string(),
-            name: "Close Window".to_string(),
-            desc: "Close focused window".to_string(),
-        },
-    ]
-}
+    // Scrolls with the wheel and selects the clicked row in the list
+    fn handle_mouse(&mut self, mouse: MouseEvent) {
+        let step = u16::try_from(self.scroll_step).unwrap_or(u16::MAX);
+        match mouse.kind {
+            MouseEventKind::ScrollUp => {
+                self.scroll_offset = self.scroll_offset.saturating_sub(step);
+            }
+            MouseEventKind::ScrollDown => {
+                self.scroll_offset = self.scroll_offset.saturating_add(step).min(self.max_scroll);
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                let area = self.content_area;
+                let inside = mouse.column >= area.x
+                    && mouse.column < area.x + area.width
+                    && mouse.row >= area.y
+                    && mouse.row < area.y + area.height;
+                if !inside {
+                    return;
+                }
+                let line = (self.scroll_offset + mouse.row - area.y) as usize;
+                let column = mouse.column - area.x;
+                if let Some(index) = self.item_lines.iter().position(|place| {
+                    (place.line..place.line + place.height).contains(&line)
+                        && (place.x..place.x + place.width).contains(&column)
+                }) {
+                    self.selected_index = index;
+                }
+            }
+            _ => {}
+        }
+    }
 
-// Returns the XDG config path, if available.
-fn xdg_config_path() -> Option<PathBuf> {
-    if let Ok(path) = std::env::var("XDG_CONFIG_HOME") {
-        return Some(PathBuf::from(path));
+    fn select_previous(&mut self) {
+        if self.wrap && self.selected_index == 0 {
+            self.cycle_selection(false);
+            return;
+        }
+        self.selected_index = self.selected_index.saturating_sub(self.scroll_step);
+        self.follow_selection = true;
     }
-    if let Ok(home) = std::env::var("HOME") {
-        return Some(PathBuf::from(home).join(".config"));
+
+    // Steps through the matches, wrapping around at either end
+    fn cycle_selection(&mut self, forward: bool) {
+        let count = self.item_lines.len();
+        if count < 2 {
+            return;
+        }
+        self.selected_index = if forward {
+            (self.selected_index + 1) % count
+        } else {
+            (self.selected_index + count - 1) % count
+        };
+        self.follow_selection = true;
     }
-    None
-}
 
-// Entry point
-fn main() -> Result<(), Box<dyn Error>> {
-    let profiling = std::env::var("NEBULA_KEYBIND_MENU_PROFILE").is_ok();
-    let start = Instant::now();
-    let mut terminal = init_terminal()?;
-    if profiling {
-        eprintln!("startup: terminal ready in {:.2?}", start.elapsed());
+    fn select_first(&mut self) {
+        self.selected_index = 0;
+        self.follow_selection = true;
     }
-    let mut app = App::new();
-    if profiling {
-        eprintln!("startup: app ready in {:.2?}", start.elapsed());
+
+    fn select_last(&mut self) {
+        self.selected_index = self.item_lines.len().saturating_sub(1);
+        self.follow_selection = true;
+    }
+
+    fn select_next(&mut self) {
+        let last = self.item_lines.len().saturating_sub(1);
+        if self.wrap && self.selected_index >= last {
+            self.cycle_selection(true);
+            return;
+        }
+        self.selected_index = (self.selected_index + self.scroll_step).min(last);
+        self.follow_selection = true;
+    }
+
+    // Lines moved by PageUp and PageDown: a screen, less the overlap
+    fn page_height(&self) -> usize {
+        self.content_height.saturating_sub(self.page_overlap).max(1) as usize
+    }
+
+    // Moves the selection up by roughly one page of lines
+    fn select_page_up(&mut self) {
+        let Some(start) = self.item_lines.get(self.selected_index).map(|p| p.line) else {
+            return;
+        };
+        let target = start.saturating_sub(self.page_height());
+        self.selected_index = self
+            .item_lines
+            .iter()
+            .position(|place| place.line >= target)
+            .unwrap_or(0);
+        self.follow_selection = true;
+    }
+
+    // Moves the selection down by roughly one page of lines
+    fn select_page_down(&mut self) {
+        let Some(start) = self.item_lines.get(self.selected_index).map(|p| p.line) else {
+            return;
+        };
+        let target = start + self.page_height();
+        self.selected_index = self
+            .item_lines
+            .iter()
+            .rposition(|place| place.line <= target)
+            .unwrap_or(self.selected_index);
+        self.follow_selection = true;
+    }
+
+    // Quits to run the selected keybind's command in launcher mode, and
+    // copies its keys otherwise
+    fn activate_selected(&mut self) {
+        self.refresh_matches();
+        let selected = self
+            .matches
+            .get(self.selected_index)
+            .map(|m| &self.items[m.index]);
+        let command = selected.and_then(|item| Some((item.command.clone()?, item.name.clone())));
+        match command {
+            Some((command, name)) if self.launcher => {
+                self.record_use(name);
+                self.launch = Some(command);
+                self.should_quit = true;
+            }
+            _ => self.copy_selected_keys(),
+        }
+    }
+
+    // Remembers a successful copy or launch of the named keybind, and counts
+    // it if usage is tracked
+    fn record_use(&mut self, name: String) {
+        if self.track_usage {
+            self.usage
+                .entry(name.clone())
+                .or_default()
+                .record(unix_now());
+        }
+        self.accepted = Some(name);
+    }
+
+    // Copies the selected keybind's keys to the system clipboard
+    fn copy_selected_keys(&mut self) {
+        self.copy_selected(|item| item.keys.clone());
+    }
+
+    // Copies the selected keybind as a "keys — name: desc" line
+    fn copy_selected_line(&mut self) {
+        self.copy_selected(|item| {
+            format!(
+                "{} — {}: {}",
+                item.keys,
+                item.name,
+                strip_markup(&item.desc)
+            )
+        });
+    }
+
+    fn copy_selected(&mut self, format: impl Fn(&Keybind) -> String) {
+        remember_query(&mut self.history, self.search_input.value());
+        self.history_index = None;
+        self.refresh_matches();
+        let Some((text, name)) = self
+            .matches
+            .get(self.selected_index)
+            .map(|m| &self.items[m.index])
+            .map(|item| (format(item), item.name.clone()))
+        else {
+            return;
+        };
+        if let Some(command) = self.copy_command.clone() {
+            // Run it off the UI thread; `run` picks up the result.
+            let (sender, result) = mpsc::channel();
+            std::thread::spawn(move || {
+                let _ = sender.send(run_copy_command(&command, &text));
+            });
+            self.copy_result = Some((result, name));
+            return;
+        }
+        match self.clipboard.copy(text, self.messages) {
+            Ok(()) => {
+                self.record_use(name);
+                self.set_status(self.messages.copied);
+            }
+            Err(err) => self.error_message = Some(err),
+        }
+    }
+
+    // Shows how a background copy command went, once it's done
+    fn check_copy_result(&mut self) {
+        let Some((result, name)) = &self.copy_result else {
+            return;
+        };
+        match result.try_recv() {
+            Ok(Ok(())) => {
+                self.record_use(name.clone());
+                self.set_status(self.messages.copied);
+            }
+            Ok(Err(err)) => self.error_message = Some(err),
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => {}
+        }
+        self.copy_result = None;
+        self.needs_redraw = true;
+    }
+
+    fn draw<B: Backend>(&mut self, terminal: &mut Terminal<B>) {
+        let start = Instant::now();
+        let refreshed = self.refresh_matches();
+        let filtered = Instant::now();
+        terminal.draw(|frame| self.render_ui(frame)).unwrap();
+        if let Some(profile) = &mut self.profile {
+            profile.record_frame(refreshed, filtered - start, filtered.elapsed());
+        }
+    }
+
+    // Renders the entire UI
+    fn render_ui(&mut self, frame: &mut Frame) {
+        const MIN_WIDTH: u16 = 20;
+        let search_height = match self.search_style {
+            SearchStyle::Bordered => 4,
+            SearchStyle::Compact => 1,
+        };
+        // Search and spacer, plus at least one content row
+        let min_height = search_height + 1 + 1;
+        let title_height = u16::from(self.show_title);
+        let footer_height = u16::from(self.show_footer);
+        let margins = self.margin.saturating_mul(2);
+        let size = frame.size();
+        if size.height < (min_height + title_height + footer_height).saturating_add(margins)
+            || size.width < MIN_WIDTH.saturating_add(margins)
+        {
+            let message = Paragraph::new(self.messages.too_small)
+                .style(Style::new().fg(self.theme.text))
+                .alignment(Alignment::Center);
+            let row = Rect::new(
+                size.x,
+                size.y + size.height / 2,
+                size.width,
+                1.min(size.height),
+            );
+            frame.render_widget(message, row);
+            return;
+        }
+
+        let main_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(self.margin)
+            .constraints(vec![
+                Constraint::Length(title_height),  // Title
+                Constraint::Length(search_height), // Search
+                Constraint::Length(1),             // Spacer
+                Constraint::Min(0),                // Content
+                Constraint::Length(footer_height), // Footer
+            ])
+            .split(frame.size());
+
+        if self.show_title {
+            self.render_title(frame, main_layout[0]);
+        }
+        self.render_search(frame, main_layout[1]);
+        self.render_error(frame, main_layout[2]);
+        self.render_content(frame, main_layout[3]);
+        if self.show_footer {
+            self.render_footer(frame, main_layout[4]);
+        }
+        if self.show_details {
+            self.render_details(frame, main_layout[3]);
+        }
+        if self.show_categories {
+            self.render_categories(frame, main_layout[3]);
+        }
+    }
+
+    // Renders the category index in a popup over `area`, each category
+    // after the label that jumps to it
+    fn render_categories(&self, frame: &mut Frame, area: Rect) {
+        let label = Style::new().fg(self.theme.dim);
+        let lines: Vec<Line> = self
+            .category_lines
+            .iter()
+            .zip(CATEGORY_LABELS.chars())
+            .map(|(&(_, first), key)| {
+                let item = &self.items[self.matches[first].index];
+                Line::from(vec![
+                    Span::styled(format!("{}  ", key), self.theme_key_style()),
+                    Span::styled(
+                        item.category.clone().unwrap_or_default(),
+                        Style::new().fg(self.theme.name),
+                    ),
+                ])
+            })
+            .collect();
+        let title = format!(" {} ", self.messages.categories_title);
+        let hint = format!(" {} ", self.messages.close_hint);
+        let content_width = lines
+            .iter()
+            .map(Line::width)
+            .chain([title.width(), hint.width()])
+            .max()
+            .unwrap_or(0);
+        // Border and one column of padding on each side.
+        let popup = centered_rect(area, content_width + 4, lines.len() + 2);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::new().fg(self.theme.border))
+            .title(Span::styled(title, Style::new().fg(self.theme.title)))
+            .title_bottom(Span::styled(hint, label))
+            .padding(Padding::horizontal(1));
+        let index = Paragraph::new(lines)
+            .block(block)
+            .style(Style::new().fg(self.theme.text));
+        frame.render_widget(Clear, popup);
+        frame.render_widget(index, popup);
+    }
+
+    // Renders the selected keybind's full details in a popup over `area`
+    fn render_details(&self, frame: &mut Frame, area: Rect) {
+        let selected = self
+            .selected_index
+            .min(self.matches.len().saturating_sub(1));
+        let Some(item) = self.matches.get(selected).map(|m| &self.items[m.index]) else {
+            return;
+        };
+        let label = Style::new().fg(self.theme.dim);
+        let conflicts = &self.conflicts[self.matches[selected].index];
+        let mut key_line = vec![
+            Span::styled(format!("{}  ", self.messages.keys), label),
+            Span::styled(item.keys.clone(), self.theme_key_style()),
+        ];
+        if !conflicts.is_empty() {
+            key_line.push(Span::styled(
+                format!(" {}", CONFLICT_MARKER.trim_end()),
+                Style::new().fg(self.theme.warning),
+            ));
+        }
+        let mut lines = vec![
+            Line::from(key_line),
+            Line::from(vec![
+                Span::styled(format!("{}  ", self.messages.name), label),
+                Span::styled(item.name.clone(), Style::new().fg(self.name_color(item))),
+            ]),
+        ];
+        if !item.desc.is_empty() {
+            let desc_style = Style::new().fg(self.theme.description);
+            let mut spans = vec![Span::styled(format!("{}  ", self.messages.desc), label)];
+            spans.extend(markup_spans(
+                &parse_markup(&item.desc),
+                &[],
+                desc_style,
+                &self.theme,
+            ));
+            lines.push(Line::from(spans));
+        }
+        if !conflicts.is_empty() {
+            let names: Vec<&str> = conflicts
+                .iter()
+                .map(|&index| self.items[index].name.as_str())
+                .collect();
+            lines.push(Line::from(vec![
+                Span::styled(format!("{}  ", self.messages.conflicts), label),
+                Span::styled(names.join(", "), Style::new().fg(self.theme.warning)),
+            ]));
+        }
+        if let Some(source) = &item.source {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{}  ", self.messages.file), label),
+                Span::styled(source.display().to_string(), label),
+            ]));
+        }
+        if let Some(notes) = &item.notes {
+            lines.push(Line::from(""));
+            lines.extend(notes.lines().map(|line| Line::from(line.to_string())));
+        }
+
+        // Border and one column of padding on each side.
+        let width = area.width.saturating_sub(4).min(70);
+        let text_width = width.saturating_sub(4).max(1) as usize;
+        let rows: usize = lines
+            .iter()
+            .map(|line| line.width().div_ceil(text_width).max(1))
+            .sum();
+        let popup = centered_rect(area, width as usize, rows + 2);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::new().fg(self.theme.border))
+            .title(Span::styled(
+                format!(" {} ", self.messages.details_title),
+                Style::new().fg(self.theme.title),
+            ))
+            .title_bottom(Span::styled(
+                format!(" {} ", self.messages.close_hint),
+                label,
+            ))
+            .padding(Padding::horizontal(1));
+        let details = Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false })
+            .style(Style::new().fg(self.theme.text));
+        frame.render_widget(Clear, popup);
+        frame.render_widget(details, popup);
+    }
+
+    // Renders key hints for the current mode, using the configured keys
+    fn render_footer(&self, frame: &mut Frame, area: Rect) {
+        // Bare characters only work in normal mode, so skip them in insert mode.
+        let key = |keys: &KeyList| {
+            keys.0
+                .iter()
+                .find(|key| self.mode == Mode::Normal || !key.is_plain_char())
+                .map_or_else(|| self.messages.unbound.to_string(), |key| key.to_string())
+        };
+        let controls = &self.controls;
+        let messages = self.messages;
+        let hints = match self.mode {
+            Mode::Insert => vec![
+                format!(
+                    "{}{} {}",
+                    key(&controls.scroll_up),
+                    key(&controls.scroll_down),
+                    messages.select
+                ),
+                format!("{} {}", key(&controls.copy), messages.copy),
+                format!("{} {}", key(&controls.details), messages.details),
+                format!("{} {}", key(&controls.toggle_regex), messages.regex),
+                format!("{} {}", key(&controls.normal_mode), messages.to_normal),
+                format!("{} {}", key(&controls.quit), messages.quit),
+            ],
+            Mode::Normal => vec![
+                format!("j/k {}", messages.select),
+                format!("y/{} {}", key(&controls.copy), messages.copy),
+                format!("{} {}", key(&controls.details), messages.details),
+                format!("i {}", messages.search),
+                format!("q/{} {}", key(&controls.normal_mode), messages.quit),
+            ],
+        };
+        let footer = Paragraph::new(hints.join(" · "))
+            .style(Style::new().fg(self.theme.dim))
+            .alignment(Alignment::Center);
+        frame.render_widget(footer, area);
+    }
+
+    // Renders the title bar
+    fn render_title(&self, frame: &mut Frame, area: Rect) {
+        let messages = self.messages;
+        let (mode_text, esc_text) = match self.mode {
+            Mode::Insert => (messages.insert_mode, messages.insert_hint),
+            Mode::Normal => (messages.normal_mode, messages.normal_hint),
+        };
+        let esc_width = esc_text.width() as u16;
+        let count_text = if self.items_loaded {
+            format!("{} / {}  ", self.matches.len(), self.items.len())
+        } else {
+            String::new()
+        };
+        let count_width = count_text.len() as u16;
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Min(0),
+                Constraint::Length(count_width),
+                Constraint::Length(esc_width),
+            ])
+            .split(area);
+
+        let title_text = if self.title_text.is_empty() {
+            messages.title
+        } else {
+            &self.title_text
+        };
+        let title = Paragraph::new(Line::from(vec![
+            Span::styled(
+                format!("{}  {}", TITLE_ICON, title_text),
+                Style::new()
+                    .fg(self.theme.title)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(format!("  {}", mode_text), Style::new().fg(self.theme.dim)),
+            Span::styled(
+                format!(
+                    "  {}: {} {}",
+                    messages.sort,
+                    messages.sort_label(self.sort_mode),
+                    if self.sort_reverse { "▼" } else { "▲" }
+                ),
+                Style::new().fg(self.theme.dim),
+            ),
+        ]))
+        .alignment(Alignment::Left);
+        frame.render_widget(title, chunks[0]);
+
+        let count = Paragraph::new(count_text)
+            .style(Style::new().fg(self.theme.text))
+            .alignment(Alignment::Right);
+        frame.render_widget(count, chunks[1]);
+
+        let esc_hint = Paragraph::new(esc_text)
+            .style(Style::new().fg(self.theme.dim))
+            .alignment(Alignment::Right);
+        frame.render_widget(esc_hint, chunks[2]);
+    }
+
+    // Renders the search input, as a box or a single row
+    fn render_search(&self, frame: &mut Frame, area: Rect) {
+        let (input_text, input_style) = if self.search_input.value().is_empty() {
+            let placeholder = if self.placeholder_text.is_empty() {
+                self.messages.placeholder
+            } else {
+                &self.placeholder_text
+            };
+            (placeholder.to_string(), Style::new().fg(self.theme.dim))
+        } else {
+            (
+                self.search_input.value().to_string(),
+                Style::new().fg(self.theme.text),
+            )
+        };
+
+        let mut labels = Vec::new();
+        if self.search_mode == SearchMode::Regex {
+            labels.push(if self.regex_valid {
+                format!(" {} ", self.messages.regex)
+            } else {
+                format!(
+                    " {} ({}) ",
+                    self.messages.regex, self.messages.regex_invalid
+                )
+            });
+        }
+        if self.case_sensitive {
+            labels.push(" Aa ".to_string());
+        }
+        let label_style = Style::new().fg(self.theme.dim);
+        let text_area = match self.search_style {
+            SearchStyle::Bordered => {
+                let input_area = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(1), Constraint::Length(3)])
+                    .split(area);
+                let mut block = Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::new().fg(self.theme.border));
+                for label in labels {
+                    block = block.title(Span::styled(label, label_style));
+                }
+                // One column of padding inside each border.
+                let inner = block.inner(input_area[1]);
+                frame.render_widget(block, input_area[1]);
+                Rect {
+                    x: inner.x + 1,
+                    width: inner.width.saturating_sub(2),
+                    ..inner
+                }
+            }
+            SearchStyle::Compact => {
+                // The prompt on the left and the labels on the right, with
+                // the input between them.
+                let labels = labels.concat();
+                let prompt_width = SEARCH_PROMPT.width() as u16;
+                let labels_width = (labels.width() as u16).min(area.width);
+                frame.render_widget(
+                    Paragraph::new(SEARCH_PROMPT).style(Style::new().fg(self.theme.border)),
+                    area,
+                );
+                frame.render_widget(
+                    Paragraph::new(Span::styled(labels, label_style)).alignment(Alignment::Right),
+                    area,
+                );
+                Rect {
+                    x: area.x + prompt_width.min(area.width),
+                    width: area.width.saturating_sub(prompt_width + labels_width),
+                    ..area
+                }
+            }
+        };
+
+        // Scroll long queries horizontally so the cursor stays in the box.
+        let scroll = self.search_input.visual_scroll(text_area.width as usize);
+        let input_paragraph =
+            Paragraph::new(Span::styled(input_text, input_style)).scroll((0, scroll as u16));
+        frame.render_widget(input_paragraph, text_area);
+        if self.mode == Mode::Insert && text_area.height > 0 {
+            let cursor = self.search_input.visual_cursor() - scroll;
+            frame.set_cursor(text_area.x + cursor as u16, text_area.y);
+        }
+    }
+
+    // Renders the last error or status, if any, in the spacer row
+    fn render_error(&self, frame: &mut Frame, area: Rect) {
+        let config_error = self
+            .config_error
+            .as_ref()
+            .map(|error| format!("{}: {}", self.messages.config_failed, error));
+        if let Some(error) = self.error_message.as_ref().or(config_error.as_ref()) {
+            let message = Paragraph::new(format!(" {}", error)).style(self.accent(Color::Red));
+            frame.render_widget(message, area);
+        } else if let Some((status, _)) = &self.status {
+            let message = Paragraph::new(format!(" {}", status)).style(self.accent(Color::Green));
+            frame.render_widget(message, area);
+        } else if self.duplicate_count > 0 {
+            let messages = self.messages;
+            let warning = format!(
+                " ⚠ {} {} ({})",
+                self.duplicate_count,
+                if self.duplicate_count == 1 {
+                    messages.duplicate_one
+                } else {
+                    messages.duplicate_many
+                },
+                messages.duplicate_hint
+            );
+            let message = Paragraph::new(warning).style(self.accent(Color::Yellow));
+            frame.render_widget(message, area);
+        }
+    }
+
+    // A fixed status color, or the default color with colors disabled
+    fn accent(&self, color: Color) -> Style {
+        if self.color {
+            Style::new().fg(color)
+        } else {
+            Style::new()
+        }
+    }
+
+    // Renders the filtered list of keybinds
+    fn render_content(&mut self, frame: &mut Frame, area: Rect) {
+        if !self.items_loaded {
+            let message =
+                Paragraph::new(self.messages.loading).style(Style::new().fg(self.theme.text));
+            frame.render_widget(message, area);
+            return;
+        }
+
+        self.content_height = area.height;
+        self.item_lines.clear();
+        self.category_lines.clear();
+        self.max_scroll = 0;
+        let filtered_items = &self.matches;
+
+        if filtered_items.is_empty() {
+            let message =
+                Paragraph::new(self.no_matches_text()).style(Style::new().fg(self.theme.text));
+            frame.render_widget(message, area);
+            return;
+        }
+        let selected = self.selected_index.min(filtered_items.len() - 1);
+
+        let (mut lines, mut item_lines, mut category_lines) =
+            self.build_lines(area.width, selected);
+        // Leave the last column for the scrollbar when the list overflows.
+        let overflows = lines.len() > area.height as usize;
+        if overflows {
+            (lines, item_lines, category_lines) =
+                self.build_lines(area.width.saturating_sub(1), selected);
+        }
+
+        if lines.is_empty() {
+            let message =
+                Paragraph::new(self.no_matches_text()).style(Style::new().fg(self.theme.text));
+            frame.render_widget(message, area);
+            return;
+        }
+
+        let max_scroll = lines.len().saturating_sub(area.height as usize);
+        let mut scroll = self.scroll_offset as usize;
+        if self.follow_selection {
+            // Scroll just enough to keep the selected item in view.
+            let ItemPlace {
+                line: sel_start,
+                height: sel_len,
+                ..
+            } = item_lines[selected];
+            if sel_start < scroll {
+                scroll = sel_start;
+            } else if sel_start + sel_len > scroll + area.height as usize {
+                scroll = (sel_start + sel_len).saturating_sub(area.height as usize);
+            }
+            self.follow_selection = false;
+        }
+        let scroll = scroll.min(max_scroll) as u16;
+        self.scroll_offset = scroll;
+        self.max_scroll = max_scroll as u16;
+        self.content_area = area;
+        self.selected_index = selected;
+        self.item_lines = item_lines;
+        self.category_lines = category_lines;
+        let list = Paragraph::new(Text::from(lines))
+            .scroll((scroll, 0))
+            .style(Style::new().fg(self.theme.text));
+        frame.render_widget(list, area);
+
+        if overflows {
+            // The thumb reaches the bottom once the last line is in view.
+            let mut scrollbar_state = ScrollbarState::new(max_scroll + 1)
+                .position(scroll as usize)
+                .viewport_content_length(area.height as usize);
+            let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None)
+                .style(Style::new().fg(self.theme.dim));
+            frame.render_stateful_widget(scrollbar, area, &mut scrollbar_state);
+        }
+    }
+
+    // The empty-list message, with the suggested name if there is one
+    fn no_matches_text(&self) -> String {
+        match &self.suggestion {
+            Some(name) => format!("{} '{}'?", self.messages.did_you_mean, name),
+            None => self.messages.no_matches.to_string(),
+        }
+    }
+
+    // Number of grid columns for a list `width` cells wide. A configured
+    // count is capped at one column per cell.
+    fn column_count(&self, width: u16) -> usize {
+        let auto = (width as usize + COLUMN_GAP) / (MIN_COLUMN_WIDTH + COLUMN_GAP);
+        match self.columns {
+            0 => auto.max(1),
+            columns => columns.clamp(1, width.max(1) as usize),
+        }
+    }
+
+    // Lays out the matches as lines at the given width, flowing them left
+    // to right across the grid columns. Also returns where each item went.
+    fn build_lines(
+        &self,
+        width: u16,
+        selected: usize,
+    ) -> (Vec<Line<'static>>, Vec<ItemPlace>, Vec<(usize, usize)>) {
+        let columns = self.column_count(width);
+        let gap = if columns > 1 { COLUMN_GAP } else { 0 };
+        let cell_width = (width as usize).saturating_sub(gap * (columns - 1)) / columns;
+        let key_column = self.key_column(cell_width);
+
+        let mut lines: Vec<Line<'static>> = Vec::new();
+        let mut item_lines = Vec::with_capacity(self.matches.len());
+        let mut row: Vec<(usize, Vec<Line<'static>>)> = Vec::new();
+        let mut category_lines = Vec::new();
+        let mut current_category = None;
+        for (index, m) in self.matches.iter().enumerate() {
+            let item = &self.items[m.index];
+            if item.category.is_some() && item.category != current_category {
+                self.flush_row(
+                    &mut row,
+                    &mut lines,
+                    &mut item_lines,
+                    cell_width,
+                    gap,
+                    selected,
+                );
+                let header = item.category.clone().unwrap_or_default();
+                category_lines.push((lines.len(), index));
+                lines.push(Line::from(Span::styled(
+                    header,
+                    Style::new()
+                        .fg(self.theme.title)
+                        .add_modifier(Modifier::BOLD),
+                )));
+                if self.spacing && !self.compact {
+                    lines.push(Line::from(" "));
+                }
+            }
+            current_category.clone_from(&item.category);
+            row.push((
+                index,
+                self.item_cell(item, m, cell_width as u16, key_column),
+            ));
+            if row.len() == columns {
+                self.flush_row(
+                    &mut row,
+                    &mut lines,
+                    &mut item_lines,
+                    cell_width,
+                    gap,
+                    selected,
+                );
+            }
+        }
+        self.flush_row(
+            &mut row,
+            &mut lines,
+            &mut item_lines,
+            cell_width,
+            gap,
+            selected,
+        );
+        (lines, item_lines, category_lines)
+    }
+
+    // Builds the lines for one item: keys and name, then the description
+    // Names start at `key_column` when it's set, and are right-aligned
+    // otherwise.
+    fn item_cell(
+        &self,
+        item: &Keybind,
+        m: &Match,
+        width: u16,
+        key_column: Option<usize>,
+    ) -> Vec<Line<'static>> {
+        let disabled = item.enabled == Some(false);
+        let conflict_text = self.conflict_marker(m.index);
+        let icon_text = key_prefix(item);
+        let key_text = format!("{} ", item.keys);
+        let key_style = self.theme_key_style();
+        let name_text = item.name.clone();
+        let name_color = self.name_color(item);
+        // Display width, not bytes, so non-ASCII text stays aligned.
+        let keys_width = conflict_text.width() + icon_text.width() + key_text.width();
+        let name_width = name_text.width();
+        let mut spans = Vec::new();
+        if !conflict_text.is_empty() {
+            spans.push(Span::styled(
+                conflict_text,
+                Style::new().fg(self.theme.warning),
+            ));
+        }
+        if !icon_text.is_empty() {
+            spans.push(Span::styled(icon_text, Style::new().fg(name_color)));
+        }
+        spans.extend(key_spans(&item.keys, &m.score.key_hits, &self.theme));
+        spans.push(Span::styled(" ", key_style));
+        let name_spans = highlight_spans(
+            &name_text,
+            &m.score.name_hits,
+            Style::new()
+                .fg(name_color)
+                .add_modifier(self.theme.name_style.modifier()),
+            self.theme.highlight,
+        );
+        let mut cell = Vec::new();
+        match place_name(keys_width, name_width, width as usize, key_column) {
+            NamePlacement::SameLine(padding) => {
+                spans.push(Span::raw(" ".repeat(padding)));
+                spans.extend(name_spans);
+                cell.push(Line::from(spans));
+            }
+            NamePlacement::Below(indent) => {
+                cell.push(Line::from(spans));
+                let mut name_line = vec![Span::raw(" ".repeat(indent))];
+                name_line.extend(name_spans);
+                cell.push(Line::from(name_line));
+            }
+        }
+        if self.show_descriptions && !self.compact && !item.desc.is_empty() {
+            cell.push(Self::make_desc_line(
+                &item.desc,
+                &m.score.desc_hits,
+                width,
+                &self.theme,
+            ));
+        }
+        if disabled {
+            for span in cell.iter_mut().flat_map(|line| line.spans.iter_mut()) {
+                span.style = span.style.fg(self.theme.dim);
+            }
+        }
+        cell
+    }
+
+    // Keys in the theme's key color and style
+    fn theme_key_style(&self) -> Style {
+        Style::new()
+            .fg(self.theme.key)
+            .add_modifier(self.theme.key_style.modifier())
+    }
+
+    // The keybind's own color if it has one and colors are on, fitted to the
+    // terminal, or the theme's name color
+    fn name_color(&self, item: &Keybind) -> Color {
+        match item.color() {
+            Some(color) if self.color => self.color_depth.fit(color),
+            _ => self.theme.name,
+        }
+    }
+
+    // Drawn before the keys of an item that shares them with another one
+    fn conflict_marker(&self, index: usize) -> &'static str {
+        if self
+            .conflicts
+            .get(index)
+            .is_some_and(|others| !others.is_empty())
+        {
+            CONFLICT_MARKER
+        } else {
+            ""
+        }
+    }
+
+    // Where left-aligned names start within a cell: the configured key
+    // width, or just past the longest key combo. At most half the cell, so
+    // names keep some room. None when names are right-aligned.
+    fn key_column(&self, cell_width: usize) -> Option<usize> {
+        if self.name_align != NameAlign::Left {
+            return None;
+        }
+        let column = if self.key_width > 0 {
+            self.key_width
+        } else {
+            self.items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| {
+                    self.conflict_marker(index).width()
+                        + key_prefix(item).width()
+                        + item.keys.width()
+                        + 2
+                })
+                .max()
+                .unwrap_or(0)
+        };
+        Some(column.min(cell_width / 2))
+    }
+
+    // Joins the cells of one grid row side by side, padding each to
+    // `cell_width`, then adds the blank line that separates rows if enabled
+    fn flush_row(
+        &self,
+        row: &mut Vec<(usize, Vec<Line<'static>>)>,
+        lines: &mut Vec<Line<'static>>,
+        item_lines: &mut Vec<ItemPlace>,
+        cell_width: usize,
+        gap: usize,
+        selected: usize,
+    ) {
+        if row.is_empty() {
+            return;
+        }
+        // Without colors, reverse video is the only way to show the selection.
+        let selected_style = if self.color {
+            Style::new().bg(self.theme.selected)
+        } else {
+            Style::new().add_modifier(Modifier::REVERSED)
+        };
+        let start = lines.len();
+        let height = row.iter().map(|(_, cell)| cell.len()).max().unwrap_or(0);
+        for offset in 0..height {
+            let mut spans = Vec::new();
+            for (column, (index, cell)) in row.iter().enumerate() {
+                if column > 0 {
+                    spans.push(Span::raw(" ".repeat(gap)));
+                }
+                let Some(line) = cell.get(offset) else {
+                    spans.push(Span::raw(" ".repeat(cell_width)));
+                    continue;
+                };
+                let mut cell_spans = fit_spans(&line.spans, cell_width);
+                if *index == selected {
+                    for span in &mut cell_spans {
+                        span.style = span.style.patch(selected_style);
+                    }
+                }
+                spans.extend(cell_spans);
+            }
+            lines.push(Line::from(spans));
+        }
+        for (column, (_, cell)) in row.drain(..).enumerate() {
+            item_lines.push(ItemPlace {
+                line: start,
+                height: cell.len(),
+                x: (column * (cell_width + gap)) as u16,
+                width: cell_width as u16,
+            });
+        }
+        if self.spacing && !self.compact {
+            lines.push(Line::from(" "));
+        }
+    }
+
+    // Re-filters the items if the query, items or sort changed since the
+    // last call, and returns whether it did. Scrolling and selection redraws
+    // reuse the cached matches.
+    fn refresh_matches(&mut self) -> bool {
+        if !self.matches_dirty {
+            return false;
+        }
+        self.matches = self.filter_items();
+        self.matches_dirty = false;
+        self.suggestion = if self.matches.is_empty() {
+            let query = split_query(self.search_input.value());
+            closest_name(&self.items, &query.text).map(str::to_string)
+        } else {
+            None
+        };
+        // Edits already select the first match; this also covers a restored
+        // session or a reload, which keep the old selection and scroll.
+        if self.auto_select_single
+            && self.matches.len() == 1
+            && !self.search_input.value().is_empty()
+        {
+            self.selected_index = 0;
+            self.follow_selection = true;
+        }
+        true
+    }
+
+    // Returns the items matching the current query, best match first
+    fn filter_items(&self) -> Vec<Match> {
+        let now = unix_now();
+        let usage: HashMap<&str, f64> = self
+            .usage
+            .iter()
+            .map(|(name, usage)| (name.as_str(), usage.weight_at(now)))
+            .collect();
+        let options = SearchOptions {
+            mode: self.search_mode,
+            case_sensitive: self.case_sensitive,
+            regex: self.regex.as_ref(),
+            sort: self.sort_mode,
+            reverse: self.sort_reverse,
+            usage: Some(&usage),
+            fold_diacritics: self.fold_diacritics,
+        };
+        filter_keybinds(&self.items, self.search_input.value(), &options)
+    }
+
+    // Creates a description line with dashes on either side
+    fn make_desc_line(desc: &str, hits: &[usize], width: u16, theme: &Theme) -> Line<'static> {
+        let desc_style = Style::new().fg(theme.description);
+        let dash_style = Style::new().fg(theme.dim);
+        let inner_width = width as usize;
+        let chars = parse_markup(desc);
+        let start = chars
+            .iter()
+            .position(|&(_, ch, _)| !ch.is_whitespace())
+            .unwrap_or(chars.len());
+        let end = chars
+            .iter()
+            .rposition(|&(_, ch, _)| !ch.is_whitespace())
+            .map_or(start, |last| last + 1);
+        let trimmed = &chars[start..end];
+
+        let desc_len: usize = trimmed
+            .iter()
+            .map(|&(_, ch, _)| ch.width().unwrap_or(0))
+            .sum();
+        let Some((left, right)) = dash_padding(inner_width, desc_len) else {
+            return Line::from(markup_spans(trimmed, hits, desc_style, theme));
+        };
+        let mut spans = vec![Span::styled(format!("{} ", "-".repeat(left)), dash_style)];
+        spans.extend(markup_spans(trimmed, hits, desc_style, theme));
+        spans.push(Span::styled(format!(" {}", "-".repeat(right)), dash_style));
+        Line::from(spans)
+    }
+}
+
+// Nerd Font keyboard glyph drawn before the title
+const TITLE_ICON: &str = "\u{f11c}";
+
+// Nerd Font search glyph that starts the search row in the compact style
+const SEARCH_PROMPT: &str = "\u{f002} ";
+
+// How long a status message stays up
+const STATUS_DURATION: Duration = Duration::from_secs(2);
+
+// Keys that pick a category in the jump index, in order
+const CATEGORY_LABELS: &str = "123456789abcdefghijklmnopqrstuvwxyz";
+
+// Where an item was drawn in the list: its first line and height, and its
+// column as an offset and width in cells
+#[derive(Clone, Copy)]
+struct ItemPlace {
+    line: usize,
+    height: usize,
+    x: u16,
+    width: u16,
+}
+
+// Readline's Alt word commands. tui_input only recognizes them with Meta,
+// which terminals report as Alt.
+fn alt_edit_request(key: &KeyEvent) -> Option<InputRequest> {
+    if key.modifiers != KeyModifiers::ALT {
+        return None;
+    }
+    match key.code {
+        KeyCode::Char('b') => Some(InputRequest::GoToPrevWord),
+        KeyCode::Char('f') => Some(InputRequest::GoToNextWord),
+        KeyCode::Char('d') => Some(InputRequest::DeleteNextWord),
+        KeyCode::Backspace => Some(InputRequest::DeletePrevWord),
+        _ => None,
+    }
+}
+
+// Puts `query` at the front of `history`, dropping any earlier copy and
+// the oldest entries past HISTORY_LIMIT
+fn remember_query(history: &mut Vec<String>, query: &str) {
+    let query = query.trim();
+    if query.is_empty() {
+        return;
+    }
+    history.retain(|past| past != query);
+    history.insert(0, query.to_string());
+    history.truncate(HISTORY_LIMIT);
+}
+
+// Marks keybinds whose keys are also bound by another keybind
+const CONFLICT_MARKER: &str = "⚠ ";
+
+// For each keybind, the indices of the others bound to the same keys, as
+// `find_duplicates` compares them
+fn find_conflicts(keybinds: &[Keybind]) -> Vec<Vec<usize>> {
+    let mut by_keys: HashMap<String, Vec<usize>> = HashMap::new();
+    for (index, item) in keybinds.iter().enumerate() {
+        by_keys
+            .entry(normalize_keys(&item.keys))
+            .or_default()
+            .push(index);
+    }
+    keybinds
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            by_keys[&normalize_keys(&item.keys)]
+                .iter()
+                .copied()
+                .filter(|&other| other != index)
+                .collect()
+        })
+        .collect()
+}
+
+// The disabled marker and icon drawn before an entry's keys
+fn key_prefix(item: &Keybind) -> String {
+    let marker = if item.enabled == Some(false) {
+        "⊘ "
+    } else {
+        ""
+    };
+    match &item.icon {
+        Some(icon) => format!("{}{} ", marker, icon),
+        None => marker.to_string(),
+    }
+}
+
+// Splits `text` into spans, styling the chars at `hits` as matches. The
+// matchers all return their positions in ascending order.
+fn highlight_spans(
+    text: &str,
+    hits: &[usize],
+    base: Style,
+    highlight_color: Color,
+) -> Vec<Span<'static>> {
+    if hits.is_empty() {
+        return vec![Span::styled(text.to_string(), base)];
+    }
+
+    let highlight = base.fg(highlight_color).add_modifier(Modifier::UNDERLINED);
+    let mut spans = Vec::new();
+    let mut run = String::new();
+    let mut run_is_hit = false;
+    for (index, ch) in text.chars().enumerate() {
+        let is_hit = hits.binary_search(&index).is_ok();
+        if is_hit != run_is_hit && !run.is_empty() {
+            let style = if run_is_hit { highlight } else { base };
+            spans.push(Span::styled(std::mem::take(&mut run), style));
+        }
+        run_is_hit = is_hit;
+        run.push(ch);
+    }
+    if !run.is_empty() {
+        let style = if run_is_hit { highlight } else { base };
+        spans.push(Span::styled(run, style));
+    }
+    spans
+}
+
+// Emphasis marked up in a description
+#[derive(Clone, Copy, PartialEq)]
+enum Emphasis {
+    Plain,
+    Bold,
+    Warning,
+}
+
+// Reads the inline markup in a description, `*bold*` and `!!warning!!`, into
+// its visible chars, each with its char position in `desc` and its emphasis.
+// A backslash makes a following `*`, `!` or `\` literal, and a marker with no
+// closing partner is kept as text.
+fn parse_markup(desc: &str) -> Vec<(usize, char, Emphasis)> {
+    let chars: Vec<char> = desc.chars().collect();
+    let is_escape = |index: usize| {
+        chars[index] == '\\' && matches!(chars.get(index + 1), Some('*' | '!' | '\\'))
+    };
+    let marker_at = |index: usize, marker: &str| {
+        marker
+            .chars()
+            .enumerate()
+            .all(|(offset, ch)| chars.get(index + offset) == Some(&ch))
+    };
+    // Where the run opened at `from` closes, skipping escaped chars
+    let closing = |from: usize, marker: &str| {
+        let mut index = from;
+        while index < chars.len() {
+            if is_escape(index) {
+                index += 2;
+            } else if marker_at(index, marker) {
+                return (index > from).then_some(index);
+            } else {
+                index += 1;
+            }
+        }
+        None
+    };
+
+    let mut visible = Vec::with_capacity(chars.len());
+    let mut emphasis = Emphasis::Plain;
+    let mut close = 0;
+    let mut index = 0;
+    while index < chars.len() {
+        if is_escape(index) {
+            visible.push((index + 1, chars[index + 1], emphasis));
+            index += 2;
+            continue;
+        }
+        if emphasis != Emphasis::Plain {
+            if index == close {
+                index += if emphasis == Emphasis::Warning { 2 } else { 1 };
+                emphasis = Emphasis::Plain;
+                continue;
+            }
+        } else if let Some((marker, kind)) = [("!!", Emphasis::Warning), ("*", Emphasis::Bold)]
+            .into_iter()
+            .find(|(marker, _)| marker_at(index, marker))
+        {
+            if let Some(end) = closing(index + marker.len(), marker) {
+                emphasis = kind;
+                close = end;
+                index += marker.len();
+                continue;
+            }
+        }
+        visible.push((index, chars[index], emphasis));
+        index += 1;
+    }
+    visible
+}
+
+// A description as plain text, without its markup
+fn strip_markup(desc: &str) -> String {
+    parse_markup(desc)
+        .into_iter()
+        .map(|(_, ch, _)| ch)
+        .collect()
+}
+
+// Splits parsed description chars into spans by emphasis, styling the chars
+// whose positions are in `hits` as matches
+fn markup_spans(
+    chars: &[(usize, char, Emphasis)],
+    hits: &[usize],
+    base: Style,
+    theme: &Theme,
+) -> Vec<Span<'static>> {
+    let highlight = Style::new()
+        .fg(theme.highlight)
+        .add_modifier(Modifier::UNDERLINED);
+    let style_for = |emphasis: Emphasis, is_hit: bool| {
+        let style = match emphasis {
+            Emphasis::Plain => base,
+            Emphasis::Bold => base.bold(),
+            Emphasis::Warning => base.fg(theme.warning),
+        };
+        if is_hit {
+            style.patch(highlight)
+        } else {
+            style
+        }
+    };
+
+    let mut spans = Vec::new();
+    let mut run = String::new();
+    let mut run_style = base;
+    for &(index, ch, emphasis) in chars {
+        let style = style_for(emphasis, hits.binary_search(&index).is_ok());
+        if style != run_style && !run.is_empty() {
+            spans.push(Span::styled(std::mem::take(&mut run), run_style));
+        }
+        run_style = style;
+        run.push(ch);
+    }
+    if !run.is_empty() {
+        spans.push(Span::styled(run, run_style));
+    }
+    spans
+}
+
+// Styles a key combo token by token: modifiers, the final key, and the `+`
+// separators each get their own color. Combos that don't split cleanly on
+// `+` (such as "CTRL + +") are drawn as a single span.
+fn combo_spans(keys: &str, hits: &[usize], theme: &Theme) -> Vec<Span<'static>> {
+    let key_style = Style::new()
+        .fg(theme.key)
+        .add_modifier(theme.key_style.modifier());
+    let tokens: Vec<&str> = keys.split('+').collect();
+    if tokens.len() < 2 || tokens.iter().any(|token| token.trim().is_empty()) {
+        return highlight_spans(keys, hits, key_style, theme.highlight);
+    }
+
+    let mut spans = Vec::new();
+    let mut offset = 0;
+    for (index, token) in tokens.iter().enumerate() {
+        if index > 0 {
+            let separator_hits: &[usize] = if hits.contains(&offset) { &[0] } else { &[] };
+            let style = Style::new().fg(theme.dim);
+            spans.extend(highlight_spans("+", separator_hits, style, theme.highlight));
+            offset += 1;
+        }
+        let style = if index + 1 < tokens.len() {
+            Style::new()
+                .fg(theme.modifier)
+                .add_modifier(theme.key_style.modifier())
+        } else {
+            key_style
+        };
+        let len = token.chars().count();
+        let token_hits = hits_within(hits, offset, len);
+        spans.extend(highlight_spans(token, &token_hits, style, theme.highlight));
+        offset += len;
+    }
+    spans
+}
+
+// Styles each step of a key sequence as a combo, with the dimmed "then"
+// between steps telling it apart from keys pressed together
+fn key_spans(keys: &str, hits: &[usize], theme: &Theme) -> Vec<Span<'static>> {
+    if !keys.contains(SEQUENCE_SEPARATOR) {
+        return combo_spans(keys, hits, theme);
+    }
+    let separator_style = Style::new().fg(theme.dim).italic();
+    let separator_len = SEQUENCE_SEPARATOR.chars().count();
+    let mut spans = Vec::new();
+    let mut offset = 0;
+    for (index, step) in keys.split(SEQUENCE_SEPARATOR).enumerate() {
+        if index > 0 {
+            let separator_hits = hits_within(hits, offset, separator_len);
+            spans.extend(highlight_spans(
+                SEQUENCE_SEPARATOR,
+                &separator_hits,
+                separator_style,
+                theme.highlight,
+            ));
+            offset += separator_len;
+        }
+        let len = step.chars().count();
+        spans.extend(combo_spans(step, &hits_within(hits, offset, len), theme));
+        offset += len;
+    }
+    spans
+}
+
+// The hits that fall in the `len` chars from `offset`, relative to `offset`
+fn hits_within(hits: &[usize], offset: usize, len: usize) -> Vec<usize> {
+    hits.iter()
+        .filter(|&&hit| hit >= offset && hit < offset + len)
+        .map(|hit| hit - offset)
+        .collect()
+}
+
+// Grid columns are at least this wide, with this many blank cells between
+const MIN_COLUMN_WIDTH: usize = 50;
+const COLUMN_GAP: usize = 3;
+
+// Owns the terminal while the TUI runs and restores it when dropped, so
+// early returns and `?` paths can't leave the shell in raw mode.
+struct TerminalGuard {
+    terminal: Tui,
+}
+
+impl TerminalGuard {
+    // Enables raw mode, then switches to the alternate screen through the
+    // same handle the backend draws with.
+    fn new() -> io::Result<Self> {
+        let setup = || {
+            let mut screen = Screen::open();
+            setup_terminal(&mut screen)?;
+            Terminal::new(CrosstermBackend::new(screen))
+        };
+        match setup() {
+            Ok(terminal) => Ok(Self { terminal }),
+            Err(err) => {
+                let _ = restore_terminal(&mut Screen::open());
+                Err(err)
+            }
+        }
+    }
+}
+
+impl std::ops::Deref for TerminalGuard {
+    type Target = Tui;
+
+    fn deref(&self) -> &Tui {
+        &self.terminal
+    }
+}
+
+impl std::ops::DerefMut for TerminalGuard {
+    fn deref_mut(&mut self) -> &mut Tui {
+        &mut self.terminal
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = restore_terminal(self.terminal.backend_mut());
+        let _ = self.terminal.show_cursor();
+    }
+}
+
+// Restores the terminal before the default panic message is printed, so the
+// message is readable and the shell isn't left in raw mode.
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal(&mut Screen::open());
+        default_hook(info);
+    }));
+}
+
+// Restores the terminal and exits on SIGINT, SIGTERM or SIGHUP, wherever
+// the main thread is at. In raw mode Ctrl+C arrives as a key instead.
+fn install_signal_handler() -> io::Result<()> {
+    use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+    let mut signals = signal_hook::iterator::Signals::new([SIGINT, SIGTERM, SIGHUP])?;
+    std::thread::spawn(move || {
+        for signal in signals.forever() {
+            // Ctrl+C in the editor is the editor's business.
+            if signal == SIGINT && EDITOR_RUNNING.load(Ordering::SeqCst) {
+                continue;
+            }
+            let _ = restore_terminal(&mut Screen::open());
+            std::process::exit(128 + signal);
+        }
+    });
+    Ok(())
+}
+
+// Set while the terminal is in raw mode on the alternate screen, so that
+// the guard, panic hook and signal handler between them restore it once
+static TERMINAL_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+// Set while the TUI is suspended for the config editor
+static EDITOR_RUNNING: AtomicBool = AtomicBool::new(false);
+
+// Enables raw mode, then switches to the alternate screen with mouse and
+// bracketed paste reporting
+fn setup_terminal(out: &mut impl Write) -> io::Result<()> {
+    enable_raw_mode()?;
+    TERMINAL_ACTIVE.store(true, Ordering::SeqCst);
+    execute!(
+        out,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )
+}
+
+// Restores terminal to original state, undoing setup in reverse order
+fn restore_terminal(out: &mut impl Write) -> io::Result<()> {
+    if !TERMINAL_ACTIVE.swap(false, Ordering::SeqCst) {
+        return Ok(());
+    }
+    execute!(
+        out,
+        DisableBracketedPaste,
+        DisableMouseCapture,
+        LeaveAlternateScreen
+    )?;
+    disable_raw_mode()
+}
+
+// Parses command-line arguments (without the program name)
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    while let Some(arg) = args.next() {
+        // Accept both `--flag value` and `--flag=value`.
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| args.next())
+                .ok_or(format!("{} requires a value", flag))
+        };
+        match flag {
+            "--config" => parsed.config = Some(PathBuf::from(value()?)),
+            "--list" => parsed.list = true,
+            "--no-state" => parsed.no_state = true,
+            "--check" => parsed.check = true,
+            "--print-path" | "--which" => parsed.print_path = true,
+            "--no-color" => parsed.no_color = true,
+            "--theme" => parsed.theme = Some(Theme::by_name(&value()?)?),
+            "--color-depth" => {
+                parsed.color_depth = match value()?.as_str() {
+                    "16" => Some(ColorDepth::Ansi16),
+                    "256" => Some(ColorDepth::Ansi256),
+                    "truecolor" => Some(ColorDepth::TrueColor),
+                    other => {
+                        return Err(format!(
+                            "unknown color depth: {} (expected 16, 256 or truecolor)",
+                            other
+                        ))
+                    }
+                }
+            }
+            "--stdin" => parsed.stdin = true,
+            "--bench" => {
+                let frames = value()?;
+                parsed.bench = match frames.parse() {
+                    Ok(frames) if frames > 0 => Some(frames),
+                    _ => return Err(format!("--bench expects a frame count, got {}", frames)),
+                };
+            }
+            "--launcher" => parsed.launcher = true,
+            "--print-selection" => parsed.print_selection = true,
+            "--show-disabled" => parsed.show_disabled = true,
+            "--init" => parsed.init = true,
+            "--force" => parsed.force = true,
+            "-h" | "--help" => parsed.help = true,
+            "-V" | "--version" => parsed.version = true,
+            "--format" => {
+                parsed.format = match value()?.as_str() {
+                    "text" => ListFormat::Text,
+                    "json" => ListFormat::Json,
+                    other => {
+                        return Err(format!("unknown format: {} (expected text or json)", other))
+                    }
+                }
+            }
+            _ => return Err(format!("unknown argument: {}", arg)),
+        }
+    }
+    if parsed.stdin && parsed.config.is_some() {
+        return Err("--stdin and --config can't be used together".to_string());
+    }
+    Ok(parsed)
+}
+
+// Writes the default keybinds as a starter config for --init, returning the
+// exit code. An existing config is printed instead unless `force` is set.
+fn run_init(explicit: Option<&Path>, force: bool) -> i32 {
+    #[derive(Serialize)]
+    struct Sample<'a> {
+        keybinds: &'a [Keybind],
+    }
+    let sample = match toml::to_string(&Sample {
+        keybinds: &default_keybinds(),
+    }) {
+        Ok(sample) => sample,
+        Err(err) => {
+            eprintln!("nebula-keybind-menu: cannot build sample config: {}", err);
+            return 1;
+        }
+    };
+    let path = match explicit {
+        Some(path) => path.to_path_buf(),
+        None => match xdg_config_path() {
+            Some(dir) => dir.join("nebula-keybind-menu").join("config.toml"),
+            None => {
+                print!("{}", sample);
+                return 0;
+            }
+        },
+    };
+    if path.exists() && !force {
+        eprintln!(
+            "nebula-keybind-menu: {} already exists; printing the sample instead (use --force to overwrite)",
+            path.display()
+        );
+        print!("{}", sample);
+        return 0;
+    }
+    let written = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| std::fs::write(&path, sample));
+    match written {
+        Ok(()) => {
+            println!("Wrote {}", path.display());
+            0
+        }
+        Err(err) => {
+            eprintln!(
+                "nebula-keybind-menu: cannot write {}: {}",
+                path.display(),
+                err
+            );
+            1
+        }
+    }
+}
+
+// Renders `frames` frames to an off-screen backend for --bench and prints
+// the load and average frame times
+fn run_bench(source: ConfigSource, frames: usize) -> io::Result<()> {
+    let mut terminal = Terminal::new(TestBackend::new(120, 40))?;
+    let mut app = App::new(source, false, true, None, ColorDepth::TrueColor, false);
+    let start = Instant::now();
+    app.load_items();
+    let loaded = start.elapsed();
+    if let Some(error) = &app.config_error {
+        eprintln!("nebula-keybind-menu: config failed to load: {}", error);
+    }
+
+    let start = Instant::now();
+    for _ in 0..frames {
+        app.draw(&mut terminal);
+    }
+    let elapsed = start.elapsed();
+    println!("keybinds: {}", app.items.len());
+    println!("load: {:.2?}", loaded);
+    println!(
+        "frames: {} in {:.2?} ({:.2?} per frame)",
+        frames,
+        elapsed,
+        elapsed / frames as u32
+    );
+    Ok(())
+}
+
+// Lists every config file that would be looked at for --print-path, and
+// what loading made of each, in the order they're tried
+fn print_config_paths(explicit: Option<&Path>, stdin: bool) {
+    if stdin {
+        println!("config: read from stdin (--stdin), no files are used");
+        return;
+    }
+    let (user, system) = trace_config(explicit);
+    let used = |layer: &[TracedPath]| {
+        layer
+            .iter()
+            .any(|traced| matches!(traced.status, PathStatus::Used | PathStatus::Empty))
+    };
+    // Configs without keybinds are used for their settings only.
+    let has_keybinds = [&user, &system].iter().any(|layer| {
+        layer
+            .iter()
+            .any(|traced| matches!(traced.status, PathStatus::Used))
+    });
+    let user_label = if explicit.is_some() {
+        "--config:"
+    } else {
+        "user config:"
+    };
+    let layers = [(user_label, &user), ("system config:", &system)];
+    for (label, layer) in layers {
+        if layer.is_empty() {
+            continue;
+        }
+        println!("{}", label);
+        for traced in layer.iter() {
+            let status = match &traced.status {
+                PathStatus::Used => "used",
+                PathStatus::Missing => "missing",
+                PathStatus::Empty => "settings",
+                PathStatus::Failed(_) => "failed",
+                PathStatus::Skipped => "not read",
+                PathStatus::Snippet => "snippet",
+                PathStatus::Include => "include",
+            };
+            println!("  {:<9} {}", status, traced.path.display());
+            if let PathStatus::Failed(err) = &traced.status {
+                println!("  {:<9} {}", "", err);
+            }
+        }
+    }
+    let result = match (used(&user), used(&system)) {
+        (true, true) => "the user config over the system config",
+        (true, false) if explicit.is_some() => "the --config file",
+        (true, false) => "the user config",
+        (false, true) => "the system config",
+        (false, false) => "the built-in default keybinds",
+    };
+    if (used(&user) || used(&system)) && !has_keybinds {
+        println!("result: {}, with the built-in keybinds", result);
+    } else {
+        println!("result: {}", result);
+    }
+}
+
+// Validates the config for --check, returning the exit code
+fn run_check(source: &ConfigSource, show_disabled: bool) -> i32 {
+    let (mut config, error) = source.load();
+    if !show_disabled {
+        config.drop_disabled();
+    }
+    let mut code = 0;
+    if let Some(error) = error {
+        eprintln!("error: {}", error);
+        code = 1;
+    }
+    if config.no_keybinds {
+        eprintln!("warning: the config contains no keybinds, so the built-in ones are used");
+    }
+    for warning in color_warnings(&config.keybinds) {
+        eprintln!("warning: {}", warning);
+    }
+    for (keys, names) in find_duplicates(&config.keybinds) {
+        eprintln!("duplicate keys \"{}\": {}", keys, names.join(", "));
+        code = 1;
+    }
+    if code == 0 {
+        println!("ok: {} keybinds, no duplicates", config.keybinds.len());
+    }
+    code
+}
+
+// Prints keybinds for scripts: tab-separated text, or JSON usable as a config
+fn print_keybinds(keybinds: &[Keybind], format: ListFormat) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    match format {
+        ListFormat::Text => {
+            for item in keybinds {
+                let desc = strip_markup(&item.desc);
+                writeln!(stdout, "{}\t{}\t{}", item.keys, item.name, desc)?;
+            }
+        }
+        ListFormat::Json => {
+            #[derive(Serialize)]
+            struct Output<'a> {
+                keybinds: &'a [Keybind],
+            }
+            serde_json::to_writer_pretty(&mut stdout, &Output { keybinds })?;
+            writeln!(stdout)?;
+        }
     }
-    app.run(&mut terminal, profiling, start)?;
-    restore_terminal()?;
     Ok(())
 }
+
+// Entry point
+fn main() -> Result<(), Box<dyn Error>> {
+    #[cfg(all(
+        unix,
+        not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+    ))]
+    if std::env::args().nth(1).as_deref() == Some(CLIPBOARD_HELPER_ARG) {
+        serve_clipboard();
+    }
+    let args = parse_args(std::env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("nebula-keybind-menu: {}\nTry --help for usage.", err);
+        std::process::exit(2);
+    });
+    if args.help {
+        print!("{}", USAGE);
+        return Ok(());
+    }
+    if args.version {
+        println!("nebula-keybind-menu {}", env!("CARGO_PKG_VERSION"));
+        return Ok(());
+    }
+    if args.init {
+        std::process::exit(run_init(args.config.as_deref(), args.force));
+    }
+    if args.print_path {
+        print_config_paths(args.config.as_deref(), args.stdin);
+        return Ok(());
+    }
+    // An explicit config must exist; don't silently fall back to defaults.
+    if let Some(path) = &args.config {
+        if let Err(err) = std::fs::File::open(path) {
+            eprintln!(
+                "nebula-keybind-menu: cannot read config {}: {}",
+                path.display(),
+                err
+            );
+            std::process::exit(1);
+        }
+    }
+
+    install_signal_handler()?;
+
+    // Read piped input up front; crossterm falls back to /dev/tty for
+    // keyboard input when stdin isn't a terminal.
+    let source = if args.stdin {
+        let mut contents = String::new();
+        io::Read::read_to_string(&mut io::stdin(), &mut contents)?;
+        ConfigSource::Stdin(contents)
+    } else {
+        ConfigSource::Files(args.config)
+    };
+
+    if args.check {
+        std::process::exit(run_check(&source, args.show_disabled));
+    }
+
+    // Renders off-screen, so it works with output redirected
+    if let Some(frames) = args.bench {
+        return Ok(run_bench(source, frames)?);
+    }
+
+    // Piped into another program, there's no screen to draw on, so print
+    // the list instead. With --print-selection the output is the selection,
+    // and the menu is drawn on the terminal.
+    if args.list || !(io::stdout().is_terminal() || args.print_selection) {
+        let (mut config, error) = source.load();
+        if !args.show_disabled {
+            config.drop_disabled();
+        }
+        if let Some(error) = error {
+            eprintln!("nebula-keybind-menu: config failed to load: {}", error);
+        }
+        if config.no_keybinds {
+            eprintln!(
+                "nebula-keybind-menu: the config contains no keybinds, listing the built-in ones"
+            );
+        }
+        match print_keybinds(&config.keybinds, args.format) {
+            // Output piped into something like `head` that exits early
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => {}
+            result => result?,
+        }
+        return Ok(());
+    }
+
+    let mut profile = std::env::var("NEBULA_KEYBIND_MENU_PROFILE")
+        .is_ok()
+        .then(Profile::default);
+    let start = Instant::now();
+    install_panic_hook();
+    let mut terminal = TerminalGuard::new()?;
+    if let Some(profile) = &mut profile {
+        profile.log(format!(
+            "startup: terminal ready in {:.2?}",
+            start.elapsed()
+        ));
+        if xdg_config_path().is_none() {
+            profile.log(
+                "config: no user config directory, as neither XDG_CONFIG_HOME nor HOME is set"
+                    .to_string(),
+            );
+        }
+    }
+    let no_color = args.no_color || std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    let mut app = App::new(
+        source,
+        !args.no_state,
+        !no_color,
+        args.theme,
+        args.color_depth.unwrap_or_else(ColorDepth::detect),
+        args.launcher,
+    );
+    app.show_disabled = args.show_disabled;
+    if let Some(profile) = &mut profile {
+        profile.log(format!("startup: app ready in {:.2?}", start.elapsed()));
+    }
+    app.profile = profile;
+    // Restore before reporting a loop error so the shell stays usable.
+    let result = app.run(&mut terminal, &mut TerminalEvents, start);
+    drop(terminal);
+    if let Some(profile) = &app.profile {
+        profile.report();
+    }
+    result?;
+    app.save_state();
+    if let Some(error) = &app.config_error {
+        eprintln!("nebula-keybind-menu: config failed to load: {}", error);
+    }
+    if let (true, Some(name)) = (args.print_selection, &app.accepted) {
+        println!("{}", name);
+    }
+    if let Some(command) = app.launch {
+        launch_command(&command).map_err(|err| format!("cannot run {}: {}", command, err))?;
+    }
+    if app.accepted.is_some() {
+        std::process::exit(EXIT_ACCEPTED);
+    }
+    Ok(())
+}
+
+// Exit status after a keybind was copied or launched, so scripts can tell
+// it apart from just closing the menu
+const EXIT_ACCEPTED: i32 = 10;
+
+// Starts a keybind's command through the shell, detached from the terminal
+// so it outlives the menu
+fn launch_command(command: &str) -> io::Result<()> {
+    let mut process = std::process::Command::new("sh");
+    process
+        .arg("-c")
+        .arg(command)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null());
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut process, 0);
+    process.spawn().map(drop)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    // Scripted input: every event is ready at once, and then none are
+    impl EventSource for VecDeque<Event> {
+        fn poll(&mut self, _timeout: Duration) -> io::Result<bool> {
+            Ok(!self.is_empty())
+        }
+
+        fn read(&mut self) -> io::Result<Event> {
+            self.pop_front()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "script ended"))
+        }
+    }
+
+    const CONFIG: &str = r#"
+lang = "en"
+
+[[keybinds]]
+keys = "SUPER + Q"
+name = "Close window"
+
+[[keybinds]]
+keys = "SUPER + RETURN"
+name = "Terminal"
+"#;
+
+    fn keys(text: &str) -> VecDeque<Event> {
+        text.chars()
+            .map(|ch| Event::Key(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE)))
+            .collect()
+    }
+
+    fn loaded_app() -> App {
+        let source = ConfigSource::Stdin(CONFIG.to_string());
+        let mut app = App::new(source, false, false, None, ColorDepth::TrueColor, false);
+        app.load_items();
+        app
+    }
+
+    // Loads the config, feeds it `events`, and returns the screen as lines
+    fn render(events: &mut VecDeque<Event>) -> Vec<String> {
+        let mut app = loaded_app();
+        if !events.is_empty() {
+            app.handle_events(events, None).unwrap();
+        }
+        let mut terminal = Terminal::new(TestBackend::new(60, 12)).unwrap();
+        app.draw(&mut terminal);
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer.get(x, y).symbol())
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn find(screen: &[String], text: &str) -> Option<usize> {
+        screen.iter().position(|line| line.contains(text))
+    }
+
+    #[test]
+    fn lists_every_keybind_in_config_order() {
+        let screen = render(&mut VecDeque::new());
+        let close = find(&screen, "Close window").expect("Close window is listed");
+        let terminal = find(&screen, "Terminal").expect("Terminal is listed");
+        assert!(close < terminal);
+        assert!(screen[close].contains("SUPER + Q"));
+        assert!(find(&screen, "2 / 2").is_some());
+    }
+
+    #[test]
+    fn typing_filters_the_list() {
+        let mut events = keys("term");
+        let screen = render(&mut events);
+        assert!(events.is_empty());
+        assert!(find(&screen, "Terminal").is_some());
+        assert!(find(&screen, "Close window").is_none());
+        assert!(find(&screen, "1 / 2").is_some());
+    }
+
+    #[test]
+    fn shows_a_message_when_nothing_matches() {
+        let screen = render(&mut keys("xyzzy"));
+        assert!(find(&screen, "No matches").is_some());
+        assert!(find(&screen, "Terminal").is_none());
+    }
+
+    #[test]
+    fn redraws_reuse_the_matches_until_the_query_changes() {
+        let mut app = loaded_app();
+        app.profile = Some(Profile::default());
+        let mut terminal = Terminal::new(TestBackend::new(60, 12)).unwrap();
+        for _ in 0..3 {
+            app.draw(&mut terminal);
+        }
+        app.handle_events(&mut keys("t"), None).unwrap();
+        app.draw(&mut terminal);
+        app.draw(&mut terminal);
+        // Moving the selection doesn't change what matches.
+        let down = Event::Key(KeyEvent::new(KeyCode::Down, KeyModifiers::NONE));
+        app.handle_events(&mut VecDeque::from([down]), None)
+            .unwrap();
+        app.draw(&mut terminal);
+
+        let profile = app.profile.unwrap();
+        assert_eq!((profile.frames, profile.filtered), (6, 2));
+    }
+
+    // Copies the selected keybind with `command` and waits for it to finish
+    fn copy_with(command: &str) -> App {
+        let mut app = loaded_app();
+        app.copy_command = Some(command.to_string());
+        app.copy_selected_keys();
+        while app.copy_result.is_some() {
+            std::thread::sleep(Duration::from_millis(10));
+            app.check_copy_result();
+        }
+        app
+    }
+
+    #[test]
+    fn only_a_successful_copy_accepts_the_keybind() {
+        let app = copy_with("cat > /dev/null");
+        assert_eq!(app.accepted.as_deref(), Some("Close window"));
+        assert!(app.error_message.is_none());
+
+        let app = copy_with("exit 1");
+        assert_eq!(app.accepted, None);
+        assert!(app.error_message.is_some());
+    }
+
+    #[test]
+    fn a_single_match_is_only_accepted_with_both_options() {
+        for (select, accept, accepted) in [
+            (false, true, false),
+            (true, false, false),
+            (true, true, true),
+        ] {
+            let mut app = loaded_app();
+            app.copy_command = Some("cat > /dev/null".to_string());
+            app.auto_select_single = select;
+            app.auto_accept_single = accept;
+            app.handle_events(&mut keys("term"), None).unwrap();
+            assert_eq!(app.copy_result.is_some(), accepted, "{} {}", select, accept);
+        }
+    }
+
+    #[test]
+    fn auto_select_single_moves_a_restored_selection_to_the_match() {
+        let mut app = loaded_app();
+        app.auto_select_single = true;
+        app.selected_index = 1;
+        app.follow_selection = false;
+        // As a restored query is applied, without an edit
+        app.search_input = Input::new("close".to_string());
+        app.matches_dirty = true;
+        app.refresh_matches();
+        assert_eq!(app.selected_index, 0);
+        assert!(app.follow_selection);
+    }
+}
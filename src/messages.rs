@@ -0,0 +1,254 @@
+use nebula_keybind_menu::SortMode;
+
+// UI strings, one table per language. To add a language, copy ENGLISH,
+// translate it, and add its code to `for_lang`.
+pub struct Messages {
+    pub title: &'static str,
+    pub insert_mode: &'static str,
+    pub normal_mode: &'static str,
+    pub insert_hint: &'static str,
+    pub normal_hint: &'static str,
+    pub sort: &'static str,
+    pub placeholder: &'static str,
+    pub loading: &'static str,
+    pub no_matches: &'static str,
+    // Followed by the suggested name in quotes and a question mark
+    pub did_you_mean: &'static str,
+    pub too_small: &'static str,
+    pub config_failed: &'static str,
+    // The config parsed, but had no keybinds
+    pub no_keybinds: &'static str,
+    pub reload_failed: &'static str,
+    pub copied: &'static str,
+    pub reloaded: &'static str,
+    pub nothing_to_reload: &'static str,
+    pub nothing_to_edit: &'static str,
+    // Between the editor and its exit status
+    pub editor_exited: &'static str,
+    // Followed by the editor and the error
+    pub editor_failed: &'static str,
+    // Followed by the error
+    pub clipboard_unavailable: &'static str,
+    pub copy_failed: &'static str,
+    // After `clipboard_unavailable`, when the background copy quit early
+    pub clipboard_helper_exited: &'static str,
+    // Shown in the search box next to the `regex` label
+    pub regex_invalid: &'static str,
+    // After the count of repeated key combos, then the hint in parentheses
+    pub duplicate_one: &'static str,
+    pub duplicate_many: &'static str,
+    pub duplicate_hint: &'static str,
+    // Sort modes, shown in the title after `sort`
+    pub sort_relevance: &'static str,
+    pub sort_config: &'static str,
+    pub sort_name: &'static str,
+    pub sort_keys: &'static str,
+    pub sort_frequent: &'static str,
+    // Footer hints, each shown after its key
+    pub select: &'static str,
+    pub copy: &'static str,
+    pub regex: &'static str,
+    pub search: &'static str,
+    pub to_normal: &'static str,
+    pub quit: &'static str,
+    pub details: &'static str,
+    // In place of the key for an action with none bound
+    pub unbound: &'static str,
+    // Details popup
+    pub details_title: &'static str,
+    pub close_hint: &'static str,
+    pub keys: &'static str,
+    pub name: &'static str,
+    pub desc: &'static str,
+    pub file: &'static str,
+    // Other keybinds bound to the same keys
+    pub conflicts: &'static str,
+    // Category index
+    pub categories_title: &'static str,
+}
+
+pub const ENGLISH: Messages = Messages {
+    title: "Keybinds",
+    insert_mode: "INSERT",
+    normal_mode: "NORMAL",
+    insert_hint: "Esc for normal mode",
+    normal_hint: "i to search, Esc to close",
+    sort: "sort",
+    placeholder: "Type to search keybinds",
+    loading: "Loading keybinds...",
+    no_matches: "No matches. Try a different query.",
+    did_you_mean: "No matches. Did you mean",
+    too_small: "Terminal too small",
+    config_failed: "Config failed to load",
+    no_keybinds: "Config loaded but contains no keybinds; showing defaults",
+    reload_failed: "Reload failed",
+    copied: "Copied to clipboard",
+    reloaded: "Config reloaded",
+    nothing_to_reload: "Nothing to reload: config was read from stdin",
+    nothing_to_edit: "Nothing to edit: config was read from stdin",
+    editor_exited: "exited with",
+    editor_failed: "cannot run",
+    clipboard_unavailable: "Clipboard unavailable",
+    copy_failed: "Copy failed",
+    clipboard_helper_exited: "the clipboard helper exited",
+    regex_invalid: "invalid, using last valid",
+    duplicate_one: "duplicate key combo",
+    duplicate_many: "duplicate key combos",
+    duplicate_hint: "run with --check for details",
+    sort_relevance: "relevance",
+    sort_config: "config",
+    sort_name: "name",
+    sort_keys: "keys",
+    sort_frequent: "frequent",
+    select: "select",
+    copy: "copy",
+    regex: "regex",
+    search: "search",
+    to_normal: "normal mode",
+    quit: "quit",
+    details: "details",
+    unbound: "unbound",
+    details_title: "Details",
+    close_hint: "Esc to close",
+    keys: "Keys",
+    name: "Name",
+    desc: "Desc",
+    file: "File",
+    conflicts: "Conflicts",
+    categories_title: "Categories",
+};
+
+pub const GERMAN: Messages = Messages {
+    title: "Tastenkürzel",
+    insert_mode: "EINGABE",
+    normal_mode: "NORMAL",
+    insert_hint: "Esc für Normalmodus",
+    normal_hint: "i zum Suchen, Esc zum Schließen",
+    sort: "Sortierung",
+    placeholder: "Tastenkürzel durchsuchen",
+    loading: "Tastenkürzel werden geladen...",
+    no_matches: "Keine Treffer. Andere Suche versuchen.",
+    did_you_mean: "Keine Treffer. Meinten Sie",
+    too_small: "Terminal zu klein",
+    config_failed: "Konfiguration konnte nicht geladen werden",
+    no_keybinds: "Konfiguration geladen, enthält aber keine Tastenkürzel; zeige Standardwerte",
+    reload_failed: "Neu laden fehlgeschlagen",
+    copied: "In die Zwischenablage kopiert",
+    reloaded: "Konfiguration neu geladen",
+    nothing_to_reload: "Nichts neu zu laden: Konfiguration kam von stdin",
+    nothing_to_edit: "Nichts zu bearbeiten: Konfiguration kam von stdin",
+    editor_exited: "beendet mit",
+    editor_failed: "Start fehlgeschlagen für",
+    clipboard_unavailable: "Zwischenablage nicht verfügbar",
+    copy_failed: "Kopieren fehlgeschlagen",
+    clipboard_helper_exited: "der Zwischenablage-Helfer wurde beendet",
+    regex_invalid: "ungültig, letzte gültige wird verwendet",
+    duplicate_one: "doppelte Tastenkombination",
+    duplicate_many: "doppelte Tastenkombinationen",
+    duplicate_hint: "Details mit --check",
+    sort_relevance: "Relevanz",
+    sort_config: "Konfiguration",
+    sort_name: "Name",
+    sort_keys: "Tasten",
+    sort_frequent: "häufig",
+    select: "auswählen",
+    copy: "kopieren",
+    regex: "Regex",
+    search: "suchen",
+    to_normal: "Normalmodus",
+    quit: "beenden",
+    details: "Details",
+    unbound: "nicht belegt",
+    details_title: "Details",
+    close_hint: "Esc zum Schließen",
+    keys: "Tasten",
+    name: "Name",
+    desc: "Info",
+    file: "Datei",
+    conflicts: "Konflikte",
+    categories_title: "Kategorien",
+};
+
+pub const SPANISH: Messages = Messages {
+    title: "Atajos",
+    insert_mode: "INSERTAR",
+    normal_mode: "NORMAL",
+    insert_hint: "Esc para modo normal",
+    normal_hint: "i para buscar, Esc para cerrar",
+    sort: "orden",
+    placeholder: "Escribe para buscar atajos",
+    loading: "Cargando atajos...",
+    no_matches: "Sin resultados. Prueba otra búsqueda.",
+    did_you_mean: "Sin resultados. ¿Quisiste decir",
+    too_small: "Terminal demasiado pequeña",
+    config_failed: "No se pudo cargar la configuración",
+    no_keybinds: "Configuración cargada pero sin atajos; se muestran los predeterminados",
+    reload_failed: "Error al recargar",
+    copied: "Copiado al portapapeles",
+    reloaded: "Configuración recargada",
+    nothing_to_reload: "Nada que recargar: la configuración se leyó de stdin",
+    nothing_to_edit: "Nada que editar: la configuración se leyó de stdin",
+    editor_exited: "terminó con",
+    editor_failed: "no se puede ejecutar",
+    clipboard_unavailable: "Portapapeles no disponible",
+    copy_failed: "Error al copiar",
+    clipboard_helper_exited: "el proceso auxiliar del portapapeles terminó",
+    regex_invalid: "inválida, se usa la última válida",
+    duplicate_one: "combinación de teclas repetida",
+    duplicate_many: "combinaciones de teclas repetidas",
+    duplicate_hint: "ejecuta con --check para ver detalles",
+    sort_relevance: "relevancia",
+    sort_config: "configuración",
+    sort_name: "nombre",
+    sort_keys: "teclas",
+    sort_frequent: "frecuente",
+    select: "seleccionar",
+    copy: "copiar",
+    regex: "regex",
+    search: "buscar",
+    to_normal: "modo normal",
+    quit: "salir",
+    details: "detalles",
+    unbound: "sin asignar",
+    details_title: "Detalles",
+    close_hint: "Esc para cerrar",
+    keys: "Teclas",
+    name: "Nombre",
+    desc: "Desc",
+    file: "Archivo",
+    conflicts: "Conflictos",
+    categories_title: "Categorías",
+};
+
+impl Messages {
+    // The name of a sort mode
+    pub fn sort_label(&self, mode: SortMode) -> &'static str {
+        match mode {
+            SortMode::Relevance => self.sort_relevance,
+            SortMode::Config => self.sort_config,
+            SortMode::Name => self.sort_name,
+            SortMode::Keys => self.sort_keys,
+            SortMode::Frequent => self.sort_frequent,
+        }
+    }
+}
+
+// Picks the table for a language code or locale such as "de" or
+// "de_DE.UTF-8", falling back to English.
+pub fn for_lang(lang: &str) -> &'static Messages {
+    let code = lang.split(['_', '.', '@', '-']).next().unwrap_or_default();
+    match code.to_ascii_lowercase().as_str() {
+        "de" => &GERMAN,
+        "es" => &SPANISH,
+        _ => &ENGLISH,
+    }
+}
+
+// The language from the environment, in the usual precedence order
+pub fn env_lang() -> String {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default()
+}
@@ -0,0 +1,173 @@
+use nebula_keybind_menu::{default_keybinds, load_config, system_config_dirs};
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard},
+};
+
+fn dirs(value: Option<&str>) -> Vec<PathBuf> {
+    system_config_dirs(value.map(OsStr::new))
+}
+
+#[test]
+fn xdg_config_dirs_come_before_the_install_prefix_in_order() {
+    assert_eq!(
+        dirs(Some("/opt/a/etc:/opt/b/etc")),
+        [
+            PathBuf::from("/opt/a/etc/nebula-keybind-menu"),
+            PathBuf::from("/opt/b/etc/nebula-keybind-menu"),
+            PathBuf::from("/usr/share/nebula-keybind-menu"),
+        ]
+    );
+}
+
+#[test]
+fn unset_or_empty_xdg_config_dirs_defaults_to_etc_xdg() {
+    let expected = [
+        PathBuf::from("/etc/xdg/nebula-keybind-menu"),
+        PathBuf::from("/usr/share/nebula-keybind-menu"),
+    ];
+    assert_eq!(dirs(None), expected);
+    assert_eq!(dirs(Some("")), expected);
+}
+
+#[test]
+fn relative_and_empty_entries_are_ignored() {
+    assert_eq!(
+        dirs(Some("relative/dir::/opt/etc")),
+        [
+            PathBuf::from("/opt/etc/nebula-keybind-menu"),
+            PathBuf::from("/usr/share/nebula-keybind-menu"),
+        ]
+    );
+}
+
+// The environment is shared by every test in this file, so the tests that
+// change it take turns.
+static ENV: Mutex<()> = Mutex::new(());
+
+// A fresh directory for one test, with every config location pointed into
+// it: `home` for the user config, `etc` for the system one
+fn isolate(name: &str) -> (PathBuf, MutexGuard<'static, ()>) {
+    let guard = ENV.lock().unwrap_or_else(|err| err.into_inner());
+    let root = std::env::temp_dir().join(format!(
+        "nebula-keybind-menu-paths-{}-{}",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    std::env::set_var("XDG_CONFIG_HOME", root.join("home"));
+    std::env::set_var("XDG_CONFIG_DIRS", root.join("etc"));
+    std::env::set_var("XDG_CACHE_HOME", root.join("cache"));
+    std::env::remove_var("NEBULA_KEYBIND_MENU_CONFIG");
+    (root, guard)
+}
+
+// Writes a config with a single keybind called `name`
+fn write_config(path: &Path, name: &str) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let contents = format!("[[keybinds]]\nkeys = \"SUPER + 1\"\nname = \"{}\"\n", name);
+    std::fs::write(path, contents).unwrap();
+}
+
+fn user_config(root: &Path) -> PathBuf {
+    root.join("home/nebula-keybind-menu/config.toml")
+}
+
+fn system_config(root: &Path) -> PathBuf {
+    root.join("etc/nebula-keybind-menu/config.toml")
+}
+
+fn loaded_names(explicit: Option<&Path>) -> Vec<String> {
+    let (config, error) = load_config(explicit);
+    assert_eq!(error, None);
+    config.keybinds.into_iter().map(|item| item.name).collect()
+}
+
+#[test]
+fn cli_path_replaces_every_other_config() {
+    let (root, _guard) = isolate("cli");
+    write_config(&root.join("cli.toml"), "Cli");
+    write_config(&root.join("env.toml"), "Env");
+    std::env::set_var("NEBULA_KEYBIND_MENU_CONFIG", root.join("env.toml"));
+    write_config(&user_config(&root), "User");
+    write_config(&system_config(&root), "System");
+
+    assert_eq!(loaded_names(Some(&root.join("cli.toml"))), ["Cli"]);
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn env_var_config_is_tried_before_the_user_config() {
+    let (root, _guard) = isolate("env");
+    write_config(&root.join("env.toml"), "Env");
+    std::env::set_var("NEBULA_KEYBIND_MENU_CONFIG", root.join("env.toml"));
+    write_config(&user_config(&root), "User");
+
+    assert_eq!(loaded_names(None), ["Env"]);
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn xdg_user_config_is_layered_over_the_system_config() {
+    let (root, _guard) = isolate("xdg");
+    write_config(&user_config(&root), "User");
+    assert_eq!(loaded_names(None), ["User"]);
+
+    write_config(&system_config(&root), "System");
+    assert_eq!(loaded_names(None), ["System", "User"]);
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn system_config_is_used_without_a_user_config() {
+    let (root, _guard) = isolate("system");
+    write_config(&system_config(&root), "System");
+
+    assert_eq!(loaded_names(None), ["System"]);
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn built_in_keybinds_are_used_without_any_config() {
+    let (root, _guard) = isolate("default");
+    let defaults: Vec<String> = default_keybinds()
+        .into_iter()
+        .map(|item| item.name)
+        .collect();
+
+    assert_eq!(loaded_names(None), defaults);
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn settings_only_user_config_is_layered_over_the_system_keybinds() {
+    let (root, _guard) = isolate("settings");
+    write_config(&system_config(&root), "System");
+    let user = user_config(&root);
+    std::fs::create_dir_all(user.parent().unwrap()).unwrap();
+    std::fs::write(&user, "footer = true\nlang = \"de\"\n").unwrap();
+
+    let (config, error) = load_config(None);
+    assert_eq!(error, None);
+    assert!(config.footer);
+    assert_eq!(config.lang.as_deref(), Some("de"));
+    let names: Vec<String> = config.keybinds.into_iter().map(|item| item.name).collect();
+    assert_eq!(names, ["System"]);
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn configs_without_keybinds_fall_back_to_the_built_in_ones() {
+    let (root, _guard) = isolate("no-keybinds");
+    let user = user_config(&root);
+    std::fs::create_dir_all(user.parent().unwrap()).unwrap();
+    std::fs::write(&user, "keybinds = []\nfooter = true\n").unwrap();
+
+    let (config, error) = load_config(None);
+    assert_eq!(error, None);
+    assert!(config.no_keybinds && config.footer);
+    assert_eq!(config.keybinds.len(), default_keybinds().len());
+    std::fs::remove_dir_all(&root).unwrap();
+}
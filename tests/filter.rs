@@ -0,0 +1,206 @@
+use nebula_keybind_menu::{filter_keybinds, Keybind, SearchMode, SearchOptions, SortMode};
+use regex::RegexBuilder;
+
+fn keybind(keys: &str, name: &str, desc: &str) -> Keybind {
+    Keybind {
+        keys: keys.to_string(),
+        name: name.to_string(),
+        desc: desc.to_string(),
+        ..Default::default()
+    }
+}
+
+fn items() -> Vec<Keybind> {
+    vec![
+        keybind("SUPER + Q", "Close window", "Close the focused window"),
+        keybind("SUPER + F", "Fullscreen", "Toggle fullscreen"),
+        keybind("SUPER + RETURN", "Terminal", "Open a terminal"),
+        keybind("SUPER + B", "Browser", "Open the web browser"),
+        keybind(
+            "SUPER + SHIFT + Q",
+            "Close all windows",
+            "Close every window",
+        ),
+    ]
+}
+
+fn names(items: &[Keybind], query: &str, options: SearchOptions) -> Vec<String> {
+    filter_keybinds(items, query, &options)
+        .into_iter()
+        .map(|m| items[m.index].name.clone())
+        .collect()
+}
+
+fn search(query: &str) -> Vec<String> {
+    names(&items(), query, SearchOptions::default())
+}
+
+#[test]
+fn empty_query_keeps_every_keybind_in_config_order() {
+    assert_eq!(
+        search(""),
+        [
+            "Close window",
+            "Fullscreen",
+            "Terminal",
+            "Browser",
+            "Close all windows"
+        ]
+    );
+}
+
+#[test]
+fn query_without_matches_returns_nothing() {
+    assert!(search("xyzzy").is_empty());
+}
+
+#[test]
+fn search_ignores_case_unless_asked_not_to() {
+    assert_eq!(search("TERMINAL"), ["Terminal"]);
+    let options = SearchOptions {
+        case_sensitive: true,
+        ..Default::default()
+    };
+    assert!(names(&items(), "TERMINAL", options).is_empty());
+    assert_eq!(names(&items(), "Terminal", options), ["Terminal"]);
+}
+
+#[test]
+fn fuzzy_search_ranks_tighter_matches_first() {
+    // A run of letters at a word start beats the same letters spread out.
+    assert_eq!(search("ter"), ["Terminal", "Browser", "Fullscreen"]);
+    assert_eq!(search("clw"), ["Close window", "Close all windows"]);
+}
+
+#[test]
+fn substring_search_needs_contiguous_text() {
+    let options = SearchOptions {
+        mode: SearchMode::Substring,
+        ..Default::default()
+    };
+    assert!(names(&items(), "clsw", options).is_empty());
+    assert_eq!(names(&items(), "open", options), ["Terminal", "Browser"]);
+}
+
+#[test]
+fn regex_search_uses_the_compiled_pattern() {
+    let regex = RegexBuilder::new("^super \\+ [fb]$")
+        .case_insensitive(true)
+        .build()
+        .unwrap();
+    let options = SearchOptions {
+        mode: SearchMode::Regex,
+        regex: Some(&regex),
+        ..Default::default()
+    };
+    assert_eq!(names(&items(), "", options).len(), 5);
+    assert_eq!(names(&items(), "x", options), ["Fullscreen", "Browser"]);
+}
+
+#[test]
+fn sort_modes_and_reverse_order_the_results() {
+    let by_name = SearchOptions {
+        sort: SortMode::Name,
+        ..Default::default()
+    };
+    assert_eq!(
+        names(&items(), "", by_name),
+        [
+            "Browser",
+            "Close all windows",
+            "Close window",
+            "Fullscreen",
+            "Terminal"
+        ]
+    );
+    let reversed = SearchOptions {
+        reverse: true,
+        ..by_name
+    };
+    assert_eq!(
+        names(&items(), "", reversed).first().map(String::as_str),
+        Some("Terminal")
+    );
+}
+
+#[test]
+fn phrases_and_exclusions_narrow_the_results() {
+    assert_eq!(search("\"every window\""), ["Close all windows"]);
+    assert_eq!(search("close -all"), ["Close window"]);
+    assert!(search("\"close\" -close").is_empty());
+}
+
+#[test]
+fn aliases_match_without_beating_the_same_name() {
+    let mut browser = keybind("SUPER + B", "Web Browser", "");
+    browser.aliases = vec!["firefox".to_string(), "internet".to_string()];
+    let items = vec![browser, keybind("SUPER + I", "Internet", "")];
+    assert_eq!(
+        names(&items, "firefox", SearchOptions::default()),
+        ["Web Browser"]
+    );
+    assert_eq!(
+        names(&items, "internet", SearchOptions::default()),
+        ["Internet", "Web Browser"]
+    );
+    assert!(names(&items, "\"web\" -firefox", SearchOptions::default()).is_empty());
+}
+
+#[test]
+fn folding_diacritics_matches_unaccented_queries() {
+    let items = vec![keybind("SUPER + C", "Café", "")];
+    assert!(names(&items, "cafe", SearchOptions::default()).is_empty());
+    let options = SearchOptions {
+        fold_diacritics: true,
+        ..Default::default()
+    };
+    assert_eq!(names(&items, "cafe", options), ["Café"]);
+}
+
+// Where the query was found in the name of each match, as char positions
+fn name_hits(items: &[Keybind], query: &str, options: SearchOptions) -> Vec<Vec<usize>> {
+    filter_keybinds(items, query, &options)
+        .into_iter()
+        .map(|m| m.score.name_hits)
+        .collect()
+}
+
+#[test]
+fn fuzzy_hits_mark_each_matched_char() {
+    let items = [keybind("SUPER + Q", "Close window", "")];
+    assert_eq!(
+        name_hits(&items, "clw", SearchOptions::default()),
+        [[0, 1, 6]]
+    );
+}
+
+#[test]
+fn substring_hits_cover_the_whole_match() {
+    let items = [keybind("SUPER + Q", "Close window", "")];
+    let options = SearchOptions {
+        mode: SearchMode::Substring,
+        ..Default::default()
+    };
+    assert_eq!(name_hits(&items, "win", options), [[6, 7, 8]]);
+}
+
+#[test]
+fn hits_count_chars_not_bytes() {
+    let items = [keybind("SUPER + E", "Éditer le fichier", "")];
+    let options = SearchOptions {
+        mode: SearchMode::Substring,
+        ..Default::default()
+    };
+    assert_eq!(name_hits(&items, "fich", options), [[10, 11, 12, 13]]);
+}
+
+#[test]
+fn folded_hits_point_at_the_accented_chars() {
+    let items = [keybind("SUPER + C", "Open Café menu", "")];
+    let options = SearchOptions {
+        mode: SearchMode::Substring,
+        fold_diacritics: true,
+        ..Default::default()
+    };
+    assert_eq!(name_hits(&items, "cafe", options), [[5, 6, 7, 8]]);
+}
@@ -0,0 +1,109 @@
+use nebula_keybind_menu::{load_config, trace_config, PathStatus};
+use std::{
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard},
+};
+
+// The environment is shared by every test in this file, so the tests take
+// turns.
+static ENV: Mutex<()> = Mutex::new(());
+
+// A fresh directory under the system temp dir for one test. The config
+// locations and the parse cache point into it, so nothing outside is read
+// or written.
+fn scratch(name: &str) -> (PathBuf, MutexGuard<'static, ()>) {
+    let guard = ENV.lock().unwrap_or_else(|err| err.into_inner());
+    let dir = std::env::temp_dir().join(format!(
+        "nebula-keybind-menu-{}-{}",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::env::set_var("XDG_CONFIG_HOME", dir.join("home"));
+    std::env::set_var("XDG_CONFIG_DIRS", dir.join("etc"));
+    std::env::set_var("XDG_CACHE_HOME", dir.join("cache"));
+    std::env::remove_var("NEBULA_KEYBIND_MENU_CONFIG");
+    (dir, guard)
+}
+
+fn write(dir: &Path, name: &str, include: &[&str], keybind: &str) {
+    let include: Vec<String> = include.iter().map(|path| format!("{:?}", path)).collect();
+    let contents = format!(
+        "include = [{}]\n[[keybinds]]\nkeys = \"SUPER+{}\"\nname = \"{}\"\n",
+        include.join(", "),
+        keybind,
+        keybind
+    );
+    std::fs::write(dir.join(name), contents).unwrap();
+}
+
+fn names(config: &Path) -> (Vec<String>, Option<String>) {
+    let (config, error) = load_config(Some(config));
+    let names = config.keybinds.into_iter().map(|item| item.name).collect();
+    (names, error)
+}
+
+#[test]
+fn includes_are_merged_depth_first_in_listed_order() {
+    let (dir, _guard) = scratch("order");
+    write(&dir, "config.toml", &["sub/a.toml", "c.toml"], "Main");
+    write(&dir, "sub/a.toml", &["b.toml"], "A");
+    write(&dir, "sub/b.toml", &[], "B");
+    write(&dir, "c.toml", &["sub/b.toml"], "C");
+
+    let (names, error) = names(&dir.join("config.toml"));
+    assert_eq!(names, ["Main", "A", "B", "C"]);
+    assert_eq!(error, None);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cycles_and_missing_files_are_skipped_with_an_error() {
+    let (dir, _guard) = scratch("cycle");
+    write(&dir, "config.toml", &["sub/a.toml", "gone.toml"], "Main");
+    write(&dir, "sub/a.toml", &["../config.toml"], "A");
+
+    let (names, error) = names(&dir.join("config.toml"));
+    assert_eq!(names, ["Main", "A"]);
+    assert!(error.unwrap().contains("loop"));
+
+    let (user, _) = trace_config(Some(&dir.join("config.toml")));
+    let statuses: Vec<&PathStatus> = user.iter().map(|traced| &traced.status).collect();
+    assert!(matches!(
+        statuses[..],
+        [
+            PathStatus::Used,
+            PathStatus::Include,
+            PathStatus::Failed(_),
+            PathStatus::Missing
+        ]
+    ));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_config_can_consist_of_includes_only() {
+    let (dir, _guard) = scratch("only");
+    std::fs::write(dir.join("config.toml"), "include = [\"sub/a.toml\"]\n").unwrap();
+    write(&dir, "sub/a.toml", &[], "A");
+
+    let (names, error) = names(&dir.join("config.toml"));
+    assert_eq!(names, ["A"]);
+    assert_eq!(error, None);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_snippet_that_is_also_included_is_merged_once() {
+    let (dir, _guard) = scratch("snippet");
+    std::fs::create_dir_all(dir.join("config.d")).unwrap();
+    write(&dir, "config.toml", &["config.d/a.toml"], "Main");
+    write(&dir, "config.d/a.toml", &[], "A");
+    write(&dir, "config.d/b.toml", &["a.toml"], "B");
+
+    let (names, error) = names(&dir.join("config.toml"));
+    assert_eq!(names, ["Main", "A", "B"]);
+    assert_eq!(error, None);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
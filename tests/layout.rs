@@ -0,0 +1,179 @@
+use nebula_keybind_menu::{centered_rect, dash_padding, fit_spans, place_name, NamePlacement};
+use ratatui::{layout::Rect, text::Span};
+use unicode_width::UnicodeWidthStr;
+
+const WIDTHS: [usize; 6] = [0, 1, 2, 3, 80, u16::MAX as usize];
+
+fn fitted_width(spans: &[Span<'static>]) -> usize {
+    spans.iter().map(|span| span.content.width()).sum()
+}
+
+#[test]
+fn dashes_fill_the_width_around_the_text() {
+    assert_eq!(dash_padding(20, 6), Some((6, 6)));
+    assert_eq!(dash_padding(21, 6), Some((6, 7)));
+    for width in WIDTHS {
+        for text_width in [0, 1, 2, width, 100_000, usize::MAX] {
+            if let Some((left, right)) = dash_padding(width, text_width) {
+                assert!(left >= 1 && right >= 1);
+                assert_eq!(left + right + text_width + 2, width);
+            }
+        }
+    }
+}
+
+#[test]
+fn no_dashes_without_room_for_one_on_each_side() {
+    assert_eq!(dash_padding(0, 0), None);
+    assert_eq!(dash_padding(3, 0), None);
+    assert_eq!(dash_padding(4, 0), Some((1, 1)));
+    assert_eq!(dash_padding(9, 6), None);
+    assert_eq!(dash_padding(usize::MAX, usize::MAX), None);
+}
+
+#[test]
+fn centered_rect_stays_inside_the_area() {
+    for width in WIDTHS {
+        for height in [0, 1, 2, u16::MAX as usize] {
+            let area = Rect::new(3, 2, width as u16, height as u16);
+            for (want_width, want_height) in [(0, 0), (10, 5), (70_000, 70_000), (usize::MAX, 1)] {
+                let rect = centered_rect(area, want_width, want_height);
+                assert!(rect.width as usize <= want_width && rect.height as usize <= want_height);
+                assert!(rect.x >= area.x && rect.right() <= area.right());
+                assert!(rect.y >= area.y && rect.bottom() <= area.bottom());
+            }
+        }
+    }
+}
+
+#[test]
+fn centered_rect_splits_the_leftover_space() {
+    let rect = centered_rect(Rect::new(0, 0, 80, 24), 40, 10);
+    assert_eq!(rect, Rect::new(20, 7, 40, 10));
+}
+
+#[test]
+fn fitted_spans_are_exactly_the_width() {
+    let long = "x".repeat(200_000);
+    let inputs: [Vec<Span<'static>>; 4] = [
+        Vec::new(),
+        vec![Span::raw("SUPER + Q"), Span::raw(" Close window")],
+        vec![Span::raw(long)],
+        vec![Span::raw("日本語のキー")],
+    ];
+    for width in WIDTHS {
+        for spans in &inputs {
+            assert_eq!(fitted_width(&fit_spans(spans, width)), width);
+        }
+    }
+}
+
+#[test]
+fn wide_chars_crossing_the_edge_become_padding() {
+    let fitted = fit_spans(&[Span::raw("日本")], 3);
+    let text: String = fitted.iter().map(|span| span.content.as_ref()).collect();
+    assert_eq!(text, "日 ");
+    let fitted = fit_spans(&[Span::raw("日本")], 1);
+    let text: String = fitted.iter().map(|span| span.content.as_ref()).collect();
+    assert_eq!(text, " ");
+}
+
+// "SUPER + SHIFT + CTRL + ALT + K " and "Kill window"
+const LONG_KEYS: usize = 31;
+const NAME: usize = 11;
+
+#[test]
+fn names_that_fit_stay_on_the_keys_line() {
+    assert_eq!(place_name(10, NAME, 40, None), NamePlacement::SameLine(19));
+    assert_eq!(
+        place_name(10, NAME, 40, Some(12)),
+        NamePlacement::SameLine(2)
+    );
+    assert_eq!(
+        place_name(LONG_KEYS, NAME, 42, None),
+        NamePlacement::SameLine(0)
+    );
+}
+
+#[test]
+fn long_keys_push_the_name_below_at_width_40() {
+    assert_eq!(
+        place_name(LONG_KEYS, NAME, 40, None),
+        NamePlacement::Below(29)
+    );
+    assert_eq!(
+        place_name(LONG_KEYS, NAME, 40, Some(12)),
+        NamePlacement::Below(12)
+    );
+}
+
+#[test]
+fn long_keys_push_the_name_below_at_width_20() {
+    assert_eq!(
+        place_name(LONG_KEYS, NAME, 20, None),
+        NamePlacement::Below(9)
+    );
+    // The key column is past where the name would still fit.
+    assert_eq!(
+        place_name(LONG_KEYS, NAME, 20, Some(12)),
+        NamePlacement::Below(9)
+    );
+    // Wider than the cell: flush left, for the caller to cut off
+    assert_eq!(place_name(LONG_KEYS, 30, 20, None), NamePlacement::Below(0));
+}
+
+// Names whose display width differs from their length in bytes and chars
+const WIDE_NAMES: [(&str, usize); 4] = [
+    ("終端を開く", 10),
+    ("🔊 Volume", 9),
+    ("Cafe\u{301} menu", 9),
+    ("→ Next → workspace", 18),
+];
+
+fn text(spans: &[Span<'static>]) -> String {
+    spans.iter().map(|span| span.content.as_ref()).collect()
+}
+
+#[test]
+fn wide_and_combining_names_are_measured_in_cells() {
+    for (name, width) in WIDE_NAMES {
+        assert_eq!(name.width(), width, "{}", name);
+    }
+}
+
+#[test]
+fn right_aligned_names_end_at_the_edge() {
+    let keys = "SUPER + T ";
+    for (name, name_width) in WIDE_NAMES {
+        let NamePlacement::SameLine(padding) = place_name(keys.width(), name.width(), 40, None)
+        else {
+            panic!("{} should fit next to the keys", name);
+        };
+        assert_eq!(keys.width() + padding + name_width, 40, "{}", name);
+    }
+    // Key combos can be wide too.
+    let keys = "SUPER + 終 ";
+    let NamePlacement::SameLine(padding) = place_name(keys.width(), 9, 20, None) else {
+        panic!("should fit");
+    };
+    assert_eq!(padding, 20 - 11 - 9);
+}
+
+#[test]
+fn dashes_around_wide_descriptions_fill_the_width() {
+    for (desc, desc_width) in WIDE_NAMES {
+        let (left, right) = dash_padding(40, desc.width()).unwrap();
+        assert_eq!(left + 1 + desc_width + 1 + right, 40, "{}", desc);
+    }
+}
+
+#[test]
+fn fitting_keeps_combining_marks_and_whole_emoji() {
+    let fitted = fit_spans(&[Span::raw("Cafe\u{301} menu")], 4);
+    assert_eq!(text(&fitted), "Cafe\u{301}");
+    let fitted = fit_spans(&[Span::raw("🔊 Volume")], 1);
+    assert_eq!(text(&fitted), " ");
+    let fitted = fit_spans(&[Span::raw("🔊 Volume")], 3);
+    assert_eq!(text(&fitted), "🔊 ");
+    assert_eq!(fitted_width(&fit_spans(&[Span::raw("終端を開く")], 5)), 5);
+}